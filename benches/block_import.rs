@@ -0,0 +1,64 @@
+// benches/block_import.rs
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use evice_blockchain::blockchain::{Block, Blockchain, Transaction, TransactionData};
+use evice_blockchain::crypto::KeyPair;
+use evice_blockchain::state::{Account, ChainParams};
+use tempfile::TempDir;
+
+const BLOCK_TRANSACTIONS: usize = 1000;
+
+fn signed_transfer(sender: &KeyPair, recipient: [u8; 1312], nonce: u64) -> Transaction {
+    let mut tx = Transaction {
+        sender: sender.public_key_bytes(),
+        data: TransactionData::Transfer { recipient, amount: 1 },
+        fee: 0,
+        nonce,
+        signer: None,
+        signature_scheme: sender.scheme,
+        signature: Vec::new(),
+    };
+    tx.signature = sender.sign(&tx.message_hash());
+    tx
+}
+
+// Chain baru yang siap menerima blok berisi BLOCK_TRANSACTIONS transfer dari `sender`.
+fn fresh_chain(sender: &KeyPair) -> (TempDir, Blockchain) {
+    let dir = tempfile::tempdir().unwrap();
+    let blockchain = Blockchain::new(dir.path().to_str().unwrap());
+    blockchain.state.set_account(&sender.public_key_bytes(), &Account::new(1_000_000)).unwrap();
+    let params = ChainParams { max_block_transactions: BLOCK_TRANSACTIONS as u64, ..ChainParams::default() };
+    blockchain.state.set_params(&params).unwrap();
+    (dir, blockchain)
+}
+
+fn block_import(c: &mut Criterion) {
+    let authority = KeyPair::new();
+    let sender = KeyPair::new();
+    let recipient = KeyPair::new().public_key_bytes();
+    let transactions: Vec<Transaction> = (0..BLOCK_TRANSACTIONS as u64)
+        .map(|nonce| signed_transfer(&sender, recipient, nonce))
+        .collect();
+    let (_dir, template) = fresh_chain(&sender);
+    let block: Block = template.create_block(&authority, transactions);
+
+    let mut group = c.benchmark_group("block_import_1000_tx");
+    group.sample_size(10);
+    for (name, parallel) in [("sequential", false), ("parallel", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let (dir, mut blockchain) = fresh_chain(&sender);
+                    blockchain.parallel_verification = parallel;
+                    (dir, blockchain, block.clone())
+                },
+                |(_dir, mut blockchain, block)| assert!(blockchain.add_block(block)),
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, block_import);
+criterion_main!(benches);
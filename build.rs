@@ -0,0 +1,22 @@
+// build.rs
+
+fn main() {
+    #[cfg(feature = "cpp_crypto")]
+    build_cpp_crypto();
+}
+
+// Mengompilasi backend Ed25519 C++ (OpenSSL) di cpp_crypto/ dan menautkannya ke libcrypto.
+#[cfg(feature = "cpp_crypto")]
+fn build_cpp_crypto() {
+    println!("cargo:rerun-if-changed=cpp_crypto/crypto_api.cpp");
+    println!("cargo:rerun-if-changed=cpp_crypto/crypto_api.h");
+
+    cc::Build::new()
+        .cpp(true)
+        .file("cpp_crypto/crypto_api.cpp")
+        .include("cpp_crypto")
+        .flag_if_supported("-std=c++17")
+        .compile("cpp_crypto");
+
+    println!("cargo:rustc-link-lib=crypto");
+}
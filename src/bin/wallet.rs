@@ -0,0 +1,345 @@
+// src/bin/wallet.rs
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use evice_blockchain::blockchain::{Transaction, TransactionData};
+use evice_blockchain::crypto::{self, KeyPair, Keystore, SignatureScheme, PUBLIC_KEY_SIZE};
+use evice_blockchain::mnemonic;
+use evice_blockchain::state::{validate_name, Address, NAME_SUFFIX};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+// Dompet baris perintah: mengelola keystore dan berbicara dengan RPC node.
+// Password keystore dibaca dari EVICE_KEYSTORE_PASSWORD atau ditanyakan di terminal.
+#[derive(Parser, Debug)]
+#[clap(version, about = "Dompet Evice", long_about = None)]
+struct Cli {
+    #[clap(long, default_value = "http://127.0.0.1:8080", global = true)]
+    rpc: String,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Membuat keystore baru (acak, atau dari mnemonic dengan --mnemonic / --restore)
+    Keygen {
+        #[clap(long)]
+        keystore: PathBuf,
+        #[clap(long, value_enum, default_value = "dilithium2")]
+        scheme: Scheme,
+        /// Buat frasa mnemonic baru dan turunkan kunci darinya
+        #[clap(long)]
+        mnemonic: bool,
+        /// Turunkan ulang kunci dari frasa mnemonic yang sudah ada
+        #[clap(long, conflicts_with = "mnemonic")]
+        restore: bool,
+        #[clap(long, default_value = "0")]
+        account: u32,
+        #[clap(long, default_value = "0")]
+        index: u32,
+    },
+    /// Menampilkan alamat sebuah keystore
+    Address {
+        #[clap(long)]
+        keystore: PathBuf,
+    },
+    /// Menampilkan saldo akun
+    Balance(Target),
+    /// Menampilkan nonce berikutnya untuk akun
+    Nonce(Target),
+    /// Menampilkan transaksi akun yang masih menunggu di mempool node
+    Pending(Target),
+    /// Mengirim koin ke alamat atau nama (*.evc)
+    Transfer {
+        #[clap(flatten)]
+        signing: Signing,
+        #[clap(long)]
+        to: String,
+        #[clap(long)]
+        amount: u64,
+    },
+    /// Melakukan stake koin
+    Stake {
+        #[clap(flatten)]
+        signing: Signing,
+        #[clap(long)]
+        amount: u64,
+    },
+    /// Menandatangani transfer atau stake tanpa menghubungi node; --nonce wajib
+    SignOffline {
+        #[clap(flatten)]
+        signing: Signing,
+        /// Penerima transfer; tanpa ini transaksi berupa stake
+        #[clap(long)]
+        to: Option<String>,
+        #[clap(long)]
+        amount: u64,
+        /// Tulis JSON transaksi ke file, bukan ke stdout
+        #[clap(long)]
+        out: Option<PathBuf>,
+    },
+    /// Menyiarkan transaksi JSON yang sudah ditandatangani
+    Broadcast {
+        file: PathBuf,
+    },
+    /// Menampilkan status transaksi berdasarkan hash
+    TxStatus {
+        hash: String,
+    },
+    /// Menampilkan sebaran fee transaksi yang sedang menunggu di mempool
+    Fees,
+}
+
+#[derive(Args, Debug)]
+struct Target {
+    /// Alamat hex; jika kosong, diambil dari --keystore
+    address: Option<String>,
+    #[clap(long)]
+    keystore: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct Signing {
+    #[clap(long)]
+    keystore: PathBuf,
+    #[clap(long, default_value = "0")]
+    fee: u64,
+    /// Nonce eksplisit; jika kosong, diambil dari node
+    #[clap(long)]
+    nonce: Option<u64>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Scheme {
+    Dilithium2,
+    Dilithium3,
+    Dilithium5,
+    Ed25519,
+    Hybrid,
+}
+
+impl From<Scheme> for SignatureScheme {
+    fn from(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::Dilithium2 => SignatureScheme::Dilithium2,
+            Scheme::Dilithium3 => SignatureScheme::Dilithium3,
+            Scheme::Dilithium5 => SignatureScheme::Dilithium5,
+            Scheme::Ed25519 => SignatureScheme::Ed25519,
+            Scheme::Hybrid => SignatureScheme::HybridDilithium2Ed25519,
+        }
+    }
+}
+
+fn parse_address(address_hex: &str) -> Result<Address, String> {
+    let mut address = [0u8; PUBLIC_KEY_SIZE];
+    hex::decode_to_slice(address_hex.trim_start_matches("0x"), &mut address)
+        .map_err(|e| format!("Alamat tidak valid: {}", e))?;
+    Ok(address)
+}
+
+fn keystore_address(path: &Path) -> Result<String, String> {
+    Keystore::load(path)?.derived_address()
+}
+
+fn unlock(path: &Path) -> Result<KeyPair, String> {
+    let password = crypto::keystore_password("Password keystore: ")?;
+    crypto::unlock_keystore(path, &password)
+}
+
+fn new_password() -> Result<String, String> {
+    let password = crypto::keystore_password("Password keystore baru: ")?;
+    if std::env::var(crypto::KEYSTORE_PASSWORD_ENV).is_err() {
+        let confirmation = crypto::keystore_password("Ulangi password: ")?;
+        if confirmation != password {
+            return Err("Password tidak sama".to_string());
+        }
+    }
+    Ok(password)
+}
+
+fn rpc_get(rpc: &str, path: &str) -> Result<serde_json::Value, String> {
+    match ureq::get(&format!("{}{}", rpc, path)).call() {
+        Ok(response) => response.into_json().map_err(|e| format!("Respons RPC tidak valid: {}", e)),
+        Err(ureq::Error::Status(code, response)) => {
+            Err(format!("RPC {} ({}): {}", path, code, response.into_string().unwrap_or_default()))
+        }
+        Err(ureq::Error::Transport(e)) => Err(format!("Gagal menghubungi node di {}: {}", rpc, e.kind())),
+    }
+}
+
+fn account_info(rpc: &str, address: &str) -> Result<serde_json::Value, String> {
+    rpc_get(rpc, &format!("/account/{}", address))
+}
+
+// Memperhitungkan transaksi akun yang masih di mempool node, sehingga beberapa transaksi
+// bisa dikirim berturut-turut tanpa menunggu blok.
+fn next_nonce(rpc: &str, address: &str) -> Result<u64, String> {
+    rpc_get(rpc, &format!("/account/{}/next_nonce", address))?["next_nonce"]
+        .as_u64()
+        .ok_or_else(|| "Respons RPC tidak berisi nonce".to_string())
+}
+
+fn broadcast(rpc: &str, tx: &Transaction) -> Result<(), String> {
+    match ureq::post(&format!("{}/transaction", rpc)).send_json(tx) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(503, response)) => {
+            let body: serde_json::Value = response.into_json().unwrap_or_default();
+            Err(format!(
+                "Mempool node penuh: {}. Coba lagi nanti atau naikkan --fee (lihat perintah `fees`).",
+                body["message"].as_str().unwrap_or("tanpa keterangan")
+            ))
+        }
+        Err(ureq::Error::Status(code, response)) => {
+            Err(format!("Transaksi ditolak ({}): {}", code, response.into_string().unwrap_or_default()))
+        }
+        Err(ureq::Error::Transport(e)) => Err(format!("Gagal menghubungi node di {}: {}", rpc, e.kind())),
+    }
+}
+
+fn transfer_data(to: &str, amount: u64) -> Result<TransactionData, String> {
+    // Penerima berupa nama (mis. alice.evc) di-resolve lewat registry nama saat blok diproses
+    if to.ends_with(NAME_SUFFIX) {
+        validate_name(to)?;
+        return Ok(TransactionData::TransferToName { name: to.to_string(), amount });
+    }
+    Ok(TransactionData::Transfer { recipient: parse_address(to)?, amount })
+}
+
+fn sign(keypair: &KeyPair, data: TransactionData, fee: u64, nonce: u64) -> Transaction {
+    let mut tx = Transaction {
+        sender: keypair.public_key_bytes(),
+        data,
+        fee,
+        nonce,
+        signer: Some(keypair.public_key()),
+        signature_scheme: keypair.scheme,
+        signature: Vec::new(),
+    };
+    tx.signature = keypair.sign(&tx.message_hash());
+    tx
+}
+
+fn sign_and_send(rpc: &str, signing: &Signing, data: TransactionData) -> Result<(), String> {
+    let keypair = unlock(&signing.keystore)?;
+    let nonce = match signing.nonce {
+        Some(nonce) => nonce,
+        None => next_nonce(rpc, &hex::encode(keypair.public_key_bytes()))?,
+    };
+    let tx = sign(&keypair, data, signing.fee, nonce);
+    broadcast(rpc, &tx)?;
+    println!("Transaksi dikirim (nonce {}): {}", nonce, hex::encode(tx.hash()));
+    Ok(())
+}
+
+fn resolve_target(target: &Target) -> Result<String, String> {
+    match (&target.address, &target.keystore) {
+        (Some(address), _) => Ok(address.trim_start_matches("0x").to_string()),
+        (None, Some(keystore)) => keystore_address(keystore),
+        (None, None) => Err("Berikan alamat atau --keystore".to_string()),
+    }
+}
+
+fn keygen(path: &Path, scheme: SignatureScheme, use_mnemonic: bool, restore: bool, account: u32, index: u32) -> Result<(), String> {
+    let keypair = if use_mnemonic || restore {
+        let phrase = if restore {
+            let phrase = rpassword::prompt_password("Frasa mnemonic: ").map_err(|e| e.to_string())?;
+            mnemonic::validate_mnemonic(&phrase)?;
+            phrase
+        } else {
+            let phrase = mnemonic::generate_mnemonic();
+            println!("Frasa mnemonic (CATAT DAN SIMPAN DI TEMPAT AMAN):\n\n  {}\n", phrase);
+            phrase
+        };
+        let passphrase = rpassword::prompt_password("Passphrase mnemonic (boleh kosong): ").map_err(|e| e.to_string())?;
+        mnemonic::derive_keypair(&phrase, &passphrase, scheme, account, index)?
+    } else {
+        KeyPair::generate(scheme)
+    };
+    crypto::import_keystore(path, &keypair, &new_password()?)?;
+    println!("Keystore disimpan di {}", path.display());
+    println!("Alamat: {}", hex::encode(keypair.public_key_bytes()));
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let rpc = cli.rpc.trim_end_matches('/');
+    match cli.command {
+        Command::Keygen { keystore, scheme, mnemonic, restore, account, index } => {
+            keygen(&keystore, scheme.into(), mnemonic, restore, account, index)
+        }
+        Command::Address { keystore } => {
+            println!("{}", keystore_address(&keystore)?);
+            Ok(())
+        }
+        Command::Balance(target) => {
+            let info = account_info(rpc, &resolve_target(&target)?)?;
+            println!("Saldo: {}", info["balance"]);
+            println!("Stake: {}", info["staked_amount"]);
+            Ok(())
+        }
+        Command::Pending(target) => {
+            let pending = rpc_get(rpc, &format!("/account/{}/pending", resolve_target(&target)?))?;
+            println!("{}", serde_json::to_string_pretty(&pending).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+        Command::Nonce(target) => {
+            println!("{}", next_nonce(rpc, &resolve_target(&target)?)?);
+            Ok(())
+        }
+        Command::Transfer { signing, to, amount } => sign_and_send(rpc, &signing, transfer_data(&to, amount)?),
+        Command::Stake { signing, amount } => sign_and_send(rpc, &signing, TransactionData::Stake { amount }),
+        Command::SignOffline { signing, to, amount, out } => {
+            let nonce = signing.nonce.ok_or("--nonce wajib untuk penandatanganan offline")?;
+            let data = match to {
+                Some(to) => transfer_data(&to, amount)?,
+                None => TransactionData::Stake { amount },
+            };
+            let tx = sign(&unlock(&signing.keystore)?, data, signing.fee, nonce);
+            let json = serde_json::to_string_pretty(&tx).map_err(|e| e.to_string())?;
+            match out {
+                Some(path) => {
+                    std::fs::write(&path, json).map_err(|e| format!("Gagal menulis {}: {}", path.display(), e))?;
+                    eprintln!("Transaksi {} ditulis ke {}", hex::encode(tx.hash()), path.display());
+                }
+                None => println!("{}", json),
+            }
+            Ok(())
+        }
+        Command::Broadcast { file } => {
+            let json = std::fs::read_to_string(&file).map_err(|e| format!("Gagal membaca {}: {}", file.display(), e))?;
+            let tx: Transaction = serde_json::from_str(&json).map_err(|e| format!("JSON transaksi tidak valid: {}", e))?;
+            broadcast(rpc, &tx)?;
+            println!("Transaksi dikirim: {}", hex::encode(tx.hash()));
+            Ok(())
+        }
+        Command::Fees => {
+            let stats = rpc_get(rpc, "/mempool/fees")?;
+            println!("{}", serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+        Command::TxStatus { hash } => {
+            let hash = hash.trim_start_matches("0x");
+            match ureq::get(&format!("{}/transaction/{}", rpc, hash)).call() {
+                Ok(response) | Err(ureq::Error::Status(404, response)) => {
+                    let status: serde_json::Value = response.into_json().map_err(|e| e.to_string())?;
+                    println!("{}", serde_json::to_string_pretty(&status).map_err(|e| e.to_string())?);
+                    Ok(())
+                }
+                Err(ureq::Error::Status(code, response)) => {
+                    Err(format!("RPC ({}): {}", code, response.into_string().unwrap_or_default()))
+                }
+                Err(ureq::Error::Transport(e)) => Err(format!("Gagal menghubungi node di {}: {}", rpc, e.kind())),
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
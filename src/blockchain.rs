@@ -1,17 +1,21 @@
 // src/blockchain.rs
 
+use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
 use sha2::{Sha256, Digest};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use log::{info, warn, error};
 
-use crate::crypto::{self, KeyPair, PUBLIC_KEY_SIZE, SIGNATURE_SIZE};
-use crate::state::{Account, Address, StateMachine};
+use crate::crypto::{self, KeyPair, SignatureScheme, TaggedPublicKey, PUBLIC_KEY_SIZE};
+use crate::sigcache::SignatureCache;
+use crate::state::{self, Address, AssetId, BlockState, ContractId, HtlcId, NftId, ParamChange, ProposalId, StateMachine};
 
-pub type PublicKey = [u8; PUBLIC_KEY_SIZE];
-pub type Signature = [u8; SIGNATURE_SIZE];
+pub type Signature = Vec<u8>;
 
+// Urutan varian menentukan encoding bincode, yang ikut di-hash ke `message_hash`.
+// Varian baru selalu ditambahkan setelah yang sudah ada.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TransactionData {
     Transfer {
@@ -22,6 +26,92 @@ pub enum TransactionData {
     Stake {
         amount: u64,
     },
+    // Transfer ke pemilik nama (mis. `alice.evc`) yang di-resolve saat blok diproses.
+    TransferToName {
+        name: String,
+        amount: u64,
+    },
+    HtlcLock {
+        #[serde(with = "serde_bytes")]
+        recipient: Address,
+        amount: u64,
+        hashlock: [u8; 32],
+        timeout_height: u64,
+    },
+    HtlcClaim {
+        htlc_id: HtlcId,
+        preimage: Vec<u8>,
+    },
+    HtlcRefund {
+        htlc_id: HtlcId,
+    },
+    DeployContract {
+        #[serde(with = "serde_bytes")]
+        code: Vec<u8>,
+        fuel_limit: u64,
+    },
+    CallContract {
+        contract_id: ContractId,
+        #[serde(with = "serde_bytes")]
+        input: Vec<u8>,
+        amount: u64,
+        fuel_limit: u64,
+    },
+    CreateAsset {
+        symbol: String,
+        decimals: u8,
+        max_supply: u64,
+    },
+    MintAsset {
+        asset_id: AssetId,
+        #[serde(with = "serde_bytes")]
+        recipient: Address,
+        amount: u64,
+    },
+    BurnAsset {
+        asset_id: AssetId,
+        amount: u64,
+    },
+    TransferAsset {
+        asset_id: AssetId,
+        #[serde(with = "serde_bytes")]
+        recipient: Address,
+        amount: u64,
+    },
+    MintNft {
+        metadata_hash: [u8; 32],
+    },
+    TransferNft {
+        nft_id: NftId,
+        #[serde(with = "serde_bytes")]
+        recipient: Address,
+    },
+    BurnNft {
+        nft_id: NftId,
+    },
+    RegisterName {
+        name: String,
+    },
+    RenewName {
+        name: String,
+    },
+    TransferName {
+        name: String,
+        #[serde(with = "serde_bytes")]
+        new_owner: Address,
+    },
+    Propose {
+        change: ParamChange,
+        activation_height: u64,
+    },
+    Vote {
+        proposal_id: ProposalId,
+        approve: bool,
+    },
+    // Ganti kunci penandatangan alamat pengirim; ditandatangani dengan kunci saat ini.
+    RotateKey {
+        new_key: TaggedPublicKey,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -31,11 +121,61 @@ pub struct Transaction {
     pub data: TransactionData,
     pub fee: u64,
     pub nonce: u64,
+    // Kunci penandatangan untuk alamat turunan (skema selain Dilithium2), lihat `signing_key`.
+    #[serde(default)]
+    pub signer: Option<TaggedPublicKey>,
+    #[serde(default)]
+    pub signature_scheme: SignatureScheme,
     #[serde(with = "serde_bytes")]
     pub signature: Signature,
 }
 
+// Layout bincode transaksi sebelum `signer`/`signature_scheme` ditambahkan. Bincode
+// tidak mengenal `#[serde(default)]`, jadi record lama (mis. di jurnal) hanya bisa
+// dibaca dengan struct ini; semuanya transaksi Dilithium2.
+#[derive(Deserialize)]
+struct LegacyTransaction {
+    #[serde(with = "serde_bytes")]
+    sender: Address,
+    data: TransactionData,
+    fee: u64,
+    nonce: u64,
+    #[serde(with = "serde_bytes")]
+    signature: Signature,
+}
+
+impl From<LegacyTransaction> for Transaction {
+    fn from(legacy: LegacyTransaction) -> Self {
+        Transaction {
+            sender: legacy.sender,
+            data: legacy.data,
+            fee: legacy.fee,
+            nonce: legacy.nonce,
+            signer: None,
+            signature_scheme: SignatureScheme::Dilithium2,
+            signature: legacy.signature,
+        }
+    }
+}
+
 impl Transaction {
+    // Decode bincode dengan layout sekarang, atau layout lama jika gagal. Layout
+    // sekarang dicoba tanpa sisa byte: pada record lama byte setelah `nonce` adalah
+    // awal panjang tanda tangan (0x74 untuk Dilithium2), bukan tag `Option` yang sah.
+    pub fn from_bincode(bytes: &[u8]) -> bincode::Result<Self> {
+        use bincode::Options;
+        let strict = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .reject_trailing_bytes();
+        match strict.deserialize::<Transaction>(bytes) {
+            Ok(tx) => Ok(tx),
+            Err(e) => match strict.deserialize::<LegacyTransaction>(bytes) {
+                Ok(legacy) => Ok(legacy.into()),
+                Err(_) => Err(e),
+            },
+        }
+    }
+
     pub fn message_hash(&self) -> Vec<u8> {
         let mut data = Vec::new();
         data.extend_from_slice(&self.sender);
@@ -48,12 +188,74 @@ impl Transaction {
         hasher.finalize().to_vec()
     }
 
-    pub fn verify(&self) -> bool {
-        let hash = self.message_hash();
-        crypto::verify(&self.sender, &hash, &self.signature)
+    // Hash transaksi lengkap termasuk tanda tangan, dipakai sebagai kunci `SignatureCache`.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.message_hash());
+        hasher.update([self.signature_scheme.tag()]);
+        hasher.update(&self.signature);
+        hasher.finalize().into()
+    }
+
+    // ID unik untuk objek yang dibuat transaksi ini (HTLC, kontrak, aset, NFT, proposal), diturunkan dari pengirim dan nonce.
+    pub fn derived_id(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.sender);
+        hasher.update(self.nonce.to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    // Kunci yang berhak menandatangani transaksi ini: kunci hasil rotasi yang
+    // terdaftar di state (lihat `StateMachine::signing_key`), kunci `signer` jika
+    // pengirim memakai alamat turunan, atau alamat itu sendiri untuk akun Dilithium2.
+    pub fn signing_key(&self, registered_key: Option<&TaggedPublicKey>) -> Option<TaggedPublicKey> {
+        if let Some(key) = registered_key {
+            return Some(key.clone());
+        }
+        if crypto::is_derived_address(&self.sender) {
+            return self
+                .signer
+                .clone()
+                .filter(|key| crypto::derive_address(key) == self.sender);
+        }
+        Some(TaggedPublicKey::from_legacy_address(&self.sender))
+    }
+
+    // Untuk kunci `HybridDilithium2Ed25519`, `signature` berisi `crypto::HybridSignature`
+    // dan komponen Dilithium maupun Ed25519 harus sama-sama valid.
+    pub fn verify(&self, registered_key: Option<&TaggedPublicKey>) -> bool {
+        let Some(signing_key) = self.signing_key(registered_key) else {
+            return false;
+        };
+        signing_key.verify(&self.message_hash(), self.signature_scheme, &self.signature)
+    }
+
+    // Seperti `verify`, tetapi melewati verifikasi jika transaksi yang sama sudah
+    // pernah lolos dengan kunci yang sama, dan mencatat hasil yang berhasil.
+    pub fn verify_cached(&self, registered_key: Option<&TaggedPublicKey>, cache: &SignatureCache) -> bool {
+        let Some(signing_key) = self.signing_key(registered_key) else {
+            return false;
+        };
+        let tx_hash = self.hash();
+        let fingerprint = key_fingerprint(&signing_key);
+        if cache.contains(&tx_hash, &fingerprint) {
+            return true;
+        }
+        if !signing_key.verify(&self.message_hash(), self.signature_scheme, &self.signature) {
+            return false;
+        }
+        cache.insert(tx_hash, fingerprint);
+        true
     }
 }
 
+fn key_fingerprint(key: &TaggedPublicKey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([key.scheme.tag()]);
+    hasher.update(&key.bytes);
+    hasher.finalize().into()
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Block {
     pub index: u64,
@@ -63,8 +265,10 @@ pub struct Block {
     pub transactions: Vec<Transaction>,
     #[serde(with = "serde_bytes")]
     pub signature: Signature,
+    #[serde(default)]
+    pub authority_scheme: SignatureScheme,
     #[serde(with = "serde_bytes")]
-    pub authority: PublicKey,
+    pub authority: Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -81,8 +285,9 @@ impl Block {
             prev_hash: vec![0; 32],
             hash: Vec::new(),
             transactions: vec![],
-            signature: [0; SIGNATURE_SIZE],
-            authority: [0; PUBLIC_KEY_SIZE],
+            signature: Vec::new(),
+            authority_scheme: SignatureScheme::Dilithium2,
+            authority: vec![0; PUBLIC_KEY_SIZE],
         };
         block.hash = Self::calculate_hash(&block);
         block
@@ -94,6 +299,10 @@ impl Block {
         data.extend_from_slice(&block.timestamp.to_be_bytes());
         data.extend_from_slice(&block.prev_hash);
         data.extend_from_slice(&block.authority);
+        // Tag skema hanya ikut di-hash untuk skema baru, agar hash blok Dilithium2 tidak berubah.
+        if block.authority_scheme != SignatureScheme::Dilithium2 {
+            data.push(block.authority_scheme.tag());
+        }
         let mut tx_hashes = Vec::new();
         for tx in &block.transactions {
             tx_hashes.extend_from_slice(&tx.message_hash());
@@ -108,9 +317,33 @@ impl Block {
     }
 }
 
+// Transaksi kandidat yang tidak dimasukkan `Blockchain::build_block`.
+#[derive(Debug, Clone)]
+pub struct ExcludedTransaction {
+    pub transaction: Transaction,
+    pub reason: String,
+    // True jika transaksi ini sendiri tidak bermasalah dan hanya tertahan karena transaksi
+    // sebelumnya dari pengirim yang sama dikecualikan; layak dikembalikan ke mempool.
+    pub retry: bool,
+}
+
+// Dipanggil setelah setiap blok berhasil di-commit, dari jalur mana pun blok itu datang
+// (dibuat sendiri, gossip, atau sinkronisasi). Dipakai mempool untuk membuang transaksi
+// yang sudah masuk blok.
+pub trait BlockObserver: Send + Sync {
+    fn on_block_added(&self, block: &Block, state: &StateMachine);
+}
+
 pub struct Blockchain {
     pub chain: Vec<Block>,
     pub state: StateMachine,
+    // Verifikasi tanda tangan transaksi dalam blok memakai thread pool rayon.
+    pub parallel_verification: bool,
+    // Dibagi dengan mempool (lihat `Mempool::with_cache`) agar transaksi tidak diverifikasi dua kali.
+    pub signature_cache: SignatureCache,
+    observers: Vec<Arc<dyn BlockObserver>>,
+    // Hash transaksi ke (indeks blok, indeks transaksi), diperbarui di `add_block`.
+    tx_index: HashMap<[u8; 32], (u64, usize)>,
 }
 
 impl Blockchain {
@@ -119,9 +352,17 @@ impl Blockchain {
         Self {
             chain: vec![Block::genesis()],
             state,
+            parallel_verification: true,
+            signature_cache: SignatureCache::default(),
+            observers: Vec::new(),
+            tx_index: HashMap::new(),
         }
     }
 
+    pub fn add_observer(&mut self, observer: Arc<dyn BlockObserver>) {
+        self.observers.push(observer);
+    }
+
     pub fn create_block(&self, authority_keypair: &KeyPair, transactions: Vec<Transaction>) -> Block {
         let last_block = self.chain.last().expect("Chain tidak boleh kosong");
         let new_index = last_block.index + 1;
@@ -136,8 +377,9 @@ impl Blockchain {
             prev_hash: last_block.hash.clone(),
             hash: Vec::new(),
             transactions,
-            signature: [0; SIGNATURE_SIZE],
-            authority: authority_keypair.public_key_bytes(),
+            signature: Vec::new(),
+            authority_scheme: authority_keypair.scheme,
+            authority: authority_keypair.public_key().bytes,
         };
 
         let hash = Block::calculate_hash(&new_block);
@@ -147,6 +389,86 @@ impl Blockchain {
         new_block
     }
 
+    // Menyusun blok dari `candidates` dengan mensimulasikan setiap transaksi terhadap
+    // satu state blok; perubahan transaksi yang gagal dibatalkan lewat
+    // `BlockState::rollback`. Transaksi yang gagal tidak dimasukkan, sehingga blok yang
+    // dihasilkan selalu lolos `add_block`. Transaksi berikutnya dari pengirim yang sama
+    // ikut dikecualikan (nonce-nya tidak lagi bersambung) dan ditandai `retry`.
+    pub fn build_block(
+        &self,
+        authority_keypair: &KeyPair,
+        candidates: Vec<Transaction>,
+    ) -> Result<(Block, Vec<ExcludedTransaction>), String> {
+        let block_height = self.chain.last().expect("Chain tidak boleh kosong").index + 1;
+        let mut scratch = BlockState::default();
+        self.state.apply_activations(block_height, &mut scratch)?;
+
+        let mut included = Vec::new();
+        let mut excluded = Vec::new();
+        let mut blocked: HashMap<Address, u64> = HashMap::new();
+        for tx in candidates {
+            if let Some(nonce) = blocked.get(&tx.sender) {
+                let reason = format!("Menunggu transaksi nonce {} dari pengirim yang sama", nonce);
+                excluded.push(ExcludedTransaction { transaction: tx, reason, retry: true });
+                continue;
+            }
+            // Kunci dibaca dari state simulasi agar RotateKey di blok yang sama ikut berlaku.
+            let signing_key = self.state.signing_key_in_block(&tx.sender, &scratch)?;
+            let result = if tx.verify_cached(signing_key.as_ref(), &self.signature_cache) {
+                scratch.begin();
+                let result = self.state.validate_transaction_in_block(&tx, block_height, &mut scratch);
+                if result.is_ok() {
+                    scratch.release();
+                } else {
+                    scratch.rollback();
+                }
+                result
+            } else {
+                Err("Tanda tangan tidak valid".to_string())
+            };
+            match result {
+                Ok(()) => included.push(tx),
+                Err(reason) => {
+                    blocked.insert(tx.sender, tx.nonce);
+                    excluded.push(ExcludedTransaction { transaction: tx, reason, retry: false });
+                }
+            }
+        }
+
+        Ok((self.create_block(authority_keypair, included), excluded))
+    }
+
+    // Posisi (indeks blok, indeks transaksi) dari transaksi yang sudah masuk chain.
+    pub fn find_transaction(&self, tx_hash: &[u8; 32]) -> Option<(u64, usize)> {
+        self.tx_index.get(tx_hash).copied()
+    }
+
+    // Kunci penandatangan tiap transaksi ditentukan berurutan (mengikuti `RotateKey`
+    // sebelumnya di blok yang sama), lalu tanda tangan diverifikasi sekaligus.
+    // Jika ada transaksi yang gagal divalidasi, seluruh blok ditolak, sehingga kunci
+    // yang dipakai di sini selalu sama dengan kunci saat transaksi diterapkan.
+    fn verify_transaction_signatures(&self, transactions: &[Transaction], temp_block_state: &BlockState) -> Result<bool, String> {
+        let mut rotated: HashMap<Address, Option<TaggedPublicKey>> = HashMap::new();
+        let mut keys = Vec::with_capacity(transactions.len());
+        for tx in transactions {
+            let key = match rotated.get(&tx.sender) {
+                Some(key) => key.clone(),
+                None => self.state.signing_key_in_block(&tx.sender, temp_block_state)?,
+            };
+            if let TransactionData::RotateKey { new_key } = &tx.data {
+                rotated.insert(tx.sender, state::rotated_signing_key(&tx.sender, new_key));
+            }
+            keys.push(key);
+        }
+
+        let verify = |(tx, key): (&Transaction, &Option<TaggedPublicKey>)| tx.verify_cached(key.as_ref(), &self.signature_cache);
+        Ok(if self.parallel_verification {
+            transactions.par_iter().zip(keys.par_iter()).all(verify)
+        } else {
+            transactions.iter().zip(keys.iter()).all(verify)
+        })
+    }
+
     pub fn add_block(&mut self, block: Block) -> bool {
         let last_block = self.chain.last().unwrap();
 
@@ -163,34 +485,66 @@ impl Blockchain {
             warn!("Validasi Gagal: Hash blok tidak valid");
             return false;
         }
-        if !crypto::verify(&block.authority, &block.hash, &block.signature) {
+        let authority_key = TaggedPublicKey { scheme: block.authority_scheme, bytes: block.authority.clone() };
+        if !authority_key.verify(&block.hash, block.authority_scheme, &block.signature) {
             warn!("Validasi Gagal: Tanda tangan authority tidak valid");
             return false;
         }
 
-        let mut temp_block_state: HashMap<Address, Account> = HashMap::new();
+        let mut temp_block_state = BlockState::default();
+        if let Err(e) = self.state.apply_activations(block.index, &mut temp_block_state) {
+            error!("KRITIS: Gagal memproses aktivasi proposal di blok {}: {}", block.index, e);
+            return false;
+        }
+        let params = match self.state.params_in_block(&temp_block_state) {
+            Ok(params) => params,
+            Err(e) => {
+                error!("KRITIS: {}", e);
+                return false;
+            }
+        };
+        if !params.is_authority(&crypto::derive_address(&authority_key)) {
+            warn!("Validasi Gagal: Pembuat blok {} bukan otoritas terdaftar", block.index);
+            return false;
+        }
+        if block.transactions.len() as u64 > params.max_block_transactions {
+            warn!(
+                "Validasi Gagal: Blok {} berisi {} transaksi (maks {})",
+                block.index,
+                block.transactions.len(),
+                params.max_block_transactions
+            );
+            return false;
+        }
 
-        for tx in &block.transactions {
-            if !tx.verify() {
+        match self.verify_transaction_signatures(&block.transactions, &temp_block_state) {
+            Ok(true) => {}
+            Ok(false) => {
                 warn!("Validasi Gagal: Tanda tangan transaksi tidak valid dalam blok {}", block.index);
                 return false;
             }
-            if let Err(e) = self.state.validate_transaction_in_block(tx, &mut temp_block_state) {
-                warn!("Validasi Gagal: Transaksi tidak valid dalam blok {}. Alasan: {}", block.index, e);
+            Err(e) => {
+                error!("KRITIS: {}", e);
                 return false;
             }
         }
 
-        let mut batch = rocksdb::WriteBatch::default();
-        for (address, account) in temp_block_state {
-            let encoded_account = bincode::serialize(&account).unwrap();
-            batch.put(address, encoded_account);
+        for tx in &block.transactions {
+            if let Err(e) = self.state.validate_transaction_in_block(tx, block.index, &mut temp_block_state) {
+                warn!("Validasi Gagal: Transaksi tidak valid dalam blok {}. Alasan: {}", block.index, e);
+                return false;
+            }
         }
 
-        if let Err(e) = self.state.db.write(batch) {
+        if let Err(e) = self.state.commit(temp_block_state) {
             error!("KRITIS: Gagal menulis batch state ke database: {}", e);
             return false;
         }
+        for (position, tx) in block.transactions.iter().enumerate() {
+            let tx_hash = tx.hash();
+            self.signature_cache.remove(&tx_hash);
+            self.tx_index.insert(tx_hash, (block.index, position));
+        }
 
         info!(
             "Blok baru #{} divalidasi dan ditambahkan ke chain dengan {} transaksi.",
@@ -198,6 +552,10 @@ impl Blockchain {
             block.transactions.len()
         );
         self.chain.push(block);
+        let block = self.chain.last().unwrap();
+        for observer in &self.observers {
+            observer.on_block_added(block, &self.state);
+        }
         true
     }
 }
@@ -205,35 +563,77 @@ impl Blockchain {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::tempdir;
+    use crate::state::{Account, ChainParams, HtlcStatus, NftEventKind, ProposalStatus, NAME_REGISTRATION_FEE, NAME_REGISTRATION_PERIOD};
+    use crate::journal::Journal;
+    use tempfile::{tempdir, TempDir};
 
-    // Helper function to create a signed transaction for tests
-    fn create_test_tx(sender_key: &KeyPair, recipient: Address, amount: u64, nonce: u64) -> Transaction {
+    fn sign_test_tx_as(sender: Address, signer: &KeyPair, data: TransactionData, nonce: u64) -> Transaction {
         let mut tx = Transaction {
-            sender: sender_key.public_key_bytes(), // <-- PERBAIKAN
-            recipient,
-            amount,
+            sender,
+            data,
+            fee: 0,
             nonce,
-            signature: [0; SIGNATURE_SIZE],
+            signer: Some(signer.public_key()),
+            signature_scheme: signer.scheme,
+            signature: Vec::new(),
         };
         let hash = tx.message_hash();
-        tx.signature = sender_key.sign(&hash); // <-- PERBAIKAN
+        tx.signature = signer.sign(&hash);
         tx
     }
 
+    fn sign_test_tx(sender_key: &KeyPair, data: TransactionData, nonce: u64) -> Transaction {
+        sign_test_tx_as(sender_key.public_key_bytes(), sender_key, data, nonce)
+    }
+
+    fn sign_test_tx_with_fee(sender_key: &KeyPair, data: TransactionData, nonce: u64, fee: u64) -> Transaction {
+        let mut tx = sign_test_tx(sender_key, data, nonce);
+        tx.fee = fee;
+        tx.signature = sender_key.sign(&tx.message_hash());
+        tx
+    }
+
+    // Helper function to create a signed transaction for tests
+    fn create_test_tx(sender_key: &KeyPair, recipient: Address, amount: u64, nonce: u64) -> Transaction {
+        sign_test_tx(sender_key, TransactionData::Transfer { recipient, amount }, nonce)
+    }
+
+    // Blockchain baru di direktori sementara beserta otoritas dan satu akun Dilithium2
+    // untuk setiap saldo di `balances`. `TempDir` harus tetap hidup selama test berjalan.
+    fn setup_chain<const N: usize>(balances: [u64; N]) -> (TempDir, Blockchain, KeyPair, [KeyPair; N]) {
+        let dir = tempdir().unwrap();
+        let blockchain = Blockchain::new(dir.path().to_str().unwrap());
+        let users = balances.map(|balance| {
+            let user = KeyPair::new();
+            blockchain.state.set_account(&user.public_key_bytes(), &Account::new(balance)).unwrap();
+            user
+        });
+        (dir, blockchain, KeyPair::new(), users)
+    }
+
+    fn add_empty_blocks(blockchain: &mut Blockchain, authority: &KeyPair, count: usize) {
+        for _ in 0..count {
+            let block = blockchain.create_block(authority, vec![]);
+            assert!(blockchain.add_block(block));
+        }
+    }
+
+    fn htlc_lock_tx(sender_key: &KeyPair, recipient: Address, preimage: &[u8], timeout_height: u64) -> Transaction {
+        let data = TransactionData::HtlcLock {
+            recipient,
+            amount: 300,
+            hashlock: Sha256::digest(preimage).into(),
+            timeout_height,
+        };
+        sign_test_tx(sender_key, data, 0)
+    }
+
     #[test]
     fn test_add_valid_block() {
         // Setup
-        let dir = tempdir().unwrap();
-        let mut blockchain = Blockchain::new(dir.path().to_str().unwrap());
-        let authority = KeyPair::new(); // <-- PERBAIKAN
-        let user1 = KeyPair::new(); // <-- PERBAIKAN
-        let user2_address = KeyPair::new().public_key_bytes(); // <-- PERBAIKAN
+        let (_dir, mut blockchain, authority, [user1]) = setup_chain([1000]);
+        let user2_address = KeyPair::new().public_key_bytes();
 
-        // Setup initial state
-        let user1_account = Account { balance: 1000, nonce: 0 };
-        blockchain.state.set_account(&user1.public_key_bytes(), &user1_account).unwrap();
-        
         let tx = create_test_tx(&user1, user2_address, 100, 0);
         let block = blockchain.create_block(&authority, vec![tx]);
 
@@ -248,11 +648,33 @@ mod tests {
         assert_eq!(updated_user1_account.nonce, 1);
     }
 
+    #[test]
+    fn test_build_block_skips_failing_transactions() {
+        let (_dir, mut blockchain, authority, [user1, user2]) = setup_chain([1000, 50]);
+        let recipient = KeyPair::new().public_key_bytes();
+
+        let ok = create_test_tx(&user1, recipient, 100, 0);
+        let overspend = create_test_tx(&user2, recipient, 100, 0);
+        let dependent = create_test_tx(&user2, recipient, 10, 1);
+        let ok_next = create_test_tx(&user1, recipient, 100, 1);
+        let (block, excluded) = blockchain
+            .build_block(&authority, vec![ok.clone(), overspend.clone(), dependent.clone(), ok_next.clone()])
+            .unwrap();
+
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(block.transactions[0].hash(), ok.hash());
+        assert_eq!(block.transactions[1].hash(), ok_next.hash());
+        assert_eq!(excluded.len(), 2);
+        assert_eq!(excluded[0].transaction.hash(), overspend.hash());
+        assert!(excluded[0].reason.contains("Saldo tidak cukup") && !excluded[0].retry);
+        assert_eq!(excluded[1].transaction.hash(), dependent.hash());
+        assert!(excluded[1].retry);
+        assert!(blockchain.add_block(block));
+    }
+
     #[test]
     fn test_reject_block_with_bad_prev_hash() {
-        let dir = tempdir().unwrap();
-        let mut blockchain = Blockchain::new(dir.path().to_str().unwrap());
-        let authority = KeyPair::new(); // <-- PERBAIKAN
+        let (_dir, mut blockchain, authority, []) = setup_chain([]);
         
         let mut block = blockchain.create_block(&authority, vec![]);
         block.prev_hash = vec![1, 2, 3];
@@ -264,10 +686,8 @@ mod tests {
 
     #[test]
     fn test_reject_block_with_bad_signature() {
-        let dir = tempdir().unwrap();
-        let mut blockchain = Blockchain::new(dir.path().to_str().unwrap());
-        let authority = KeyPair::new(); // <-- PERBAIKAN
-        let fake_authority = KeyPair::new(); // <-- PERBAIKAN
+        let (_dir, mut blockchain, authority, []) = setup_chain([]);
+        let fake_authority = KeyPair::new();
         
         let mut block = blockchain.create_block(&authority, vec![]);
         let hash = Block::calculate_hash(&block);
@@ -280,18 +700,9 @@ mod tests {
 
     #[test]
     fn test_atomic_revert_on_invalid_transaction() {
-        let dir = tempdir().unwrap();
-        let mut blockchain = Blockchain::new(dir.path().to_str().unwrap());
-        let authority = KeyPair::new(); // <-- PERBAIKAN
-        let user1 = KeyPair::new(); // <-- PERBAIKAN
-        let user2 = KeyPair::new(); // <-- PERBAIKAN
-        let user3_address = KeyPair::new().public_key_bytes(); // <-- PERBAIKAN
-
-        let user1_account = Account { balance: 1000, nonce: 0 };
-        blockchain.state.set_account(&user1.public_key_bytes(), &user1_account).unwrap();
-        let user2_account = Account { balance: 50, nonce: 0 };
-        blockchain.state.set_account(&user2.public_key_bytes(), &user2_account).unwrap();
-        
+        let (_dir, mut blockchain, authority, [user1, user2]) = setup_chain([1000, 50]);
+        let user3_address = KeyPair::new().public_key_bytes();
+
         let valid_tx = create_test_tx(&user1, user3_address, 100, 0);
         let invalid_tx = create_test_tx(&user2, user3_address, 100, 0);
         
@@ -305,4 +716,601 @@ mod tests {
         assert_eq!(user1_account_after.balance, 1000);
         assert_eq!(user1_account_after.nonce, 0);
     }
+
+    #[test]
+    fn test_htlc_lock_and_claim_with_preimage() {
+        let (_dir, mut blockchain, authority, [alice, bob]) = setup_chain([1000, 0]);
+
+        // Jumlah + biaya yang melebihi u64 ditolak, bukan overflow.
+        let data = TransactionData::HtlcLock { recipient: bob.public_key_bytes(), amount: u64::MAX, hashlock: [0; 32], timeout_height: 10 };
+        let overflowing = sign_test_tx_with_fee(&alice, data, 0, 1);
+        let block = blockchain.create_block(&authority, vec![overflowing]);
+        assert!(!blockchain.add_block(block));
+
+        let lock_tx = htlc_lock_tx(&alice, bob.public_key_bytes(), b"rahasia", 10);
+        let htlc_id = lock_tx.derived_id();
+        let block = blockchain.create_block(&authority, vec![lock_tx]);
+        assert!(blockchain.add_block(block));
+
+        let alice_account = blockchain.state.get_account(&alice.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(alice_account.balance, 700);
+        assert_eq!(blockchain.state.get_htlc(&htlc_id).unwrap().unwrap().status, HtlcStatus::Locked);
+
+        let wrong_claim = sign_test_tx(&bob, TransactionData::HtlcClaim { htlc_id, preimage: b"salah".to_vec() }, 0);
+        let block = blockchain.create_block(&authority, vec![wrong_claim]);
+        assert!(!blockchain.add_block(block));
+
+        let claim = sign_test_tx(&bob, TransactionData::HtlcClaim { htlc_id, preimage: b"rahasia".to_vec() }, 0);
+        let block = blockchain.create_block(&authority, vec![claim]);
+        assert!(blockchain.add_block(block));
+
+        let bob_account = blockchain.state.get_account(&bob.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(bob_account.balance, 300);
+        let htlc = blockchain.state.get_htlc(&htlc_id).unwrap().unwrap();
+        assert_eq!(htlc.status, HtlcStatus::Claimed { preimage: b"rahasia".to_vec() });
+    }
+
+    #[test]
+    fn test_htlc_refund_only_after_timeout() {
+        let (_dir, mut blockchain, authority, [alice]) = setup_chain([1000]);
+        let bob_address = KeyPair::new().public_key_bytes();
+
+        let lock_tx = htlc_lock_tx(&alice, bob_address, b"rahasia", 3);
+        let htlc_id = lock_tx.derived_id();
+        let block = blockchain.create_block(&authority, vec![lock_tx]);
+        assert!(blockchain.add_block(block));
+
+        let early_refund = sign_test_tx(&alice, TransactionData::HtlcRefund { htlc_id }, 1);
+        let block = blockchain.create_block(&authority, vec![early_refund.clone()]);
+        assert!(!blockchain.add_block(block));
+
+        add_empty_blocks(&mut blockchain, &authority, 1);
+        let block = blockchain.create_block(&authority, vec![early_refund]);
+        assert_eq!(block.index, 3);
+        assert!(blockchain.add_block(block));
+
+        let alice_account = blockchain.state.get_account(&alice.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(alice_account.balance, 1000);
+        assert_eq!(blockchain.state.get_htlc(&htlc_id).unwrap().unwrap().status, HtlcStatus::Refunded);
+    }
+
+    const COUNTER_WAT: &str = r#"
+        (module
+          (import "env" "storage_read" (func $read (param i32 i32 i32 i32) (result i32)))
+          (import "env" "storage_write" (func $write (param i32 i32 i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "count")
+          (func (export "init") (result i32)
+            (i64.store (i32.const 16) (i64.const 0))
+            (call $write (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 8))
+            (i32.const 0))
+          (func (export "call") (result i32)
+            (drop (call $read (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 8)))
+            (i64.store (i32.const 16) (i64.add (i64.load (i32.const 16)) (i64.const 1)))
+            (call $write (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 8))
+            (i32.const 0)))
+    "#;
+
+    fn deploy_tx(sender_key: &KeyPair, wat_source: &str, nonce: u64) -> Transaction {
+        let code = wat::parse_str(wat_source).unwrap();
+        sign_test_tx(sender_key, TransactionData::DeployContract { code, fuel_limit: 100_000 }, nonce)
+    }
+
+    #[test]
+    fn test_contract_deploy_and_call_updates_storage() {
+        let (_dir, mut blockchain, authority, [user]) = setup_chain([1000]);
+
+        let deploy = deploy_tx(&user, COUNTER_WAT, 0);
+        let contract_id = deploy.derived_id();
+        let call = |nonce| {
+            let data = TransactionData::CallContract { contract_id, input: vec![], amount: 10, fuel_limit: 100_000 };
+            sign_test_tx(&user, data, nonce)
+        };
+        let second_call = call(2);
+        let second_call_hash = second_call.hash();
+        let block = blockchain.create_block(&authority, vec![deploy, call(1), second_call]);
+        assert!(blockchain.add_block(block));
+        assert_eq!(blockchain.find_transaction(&second_call_hash), Some((1, 2)));
+        assert_eq!(blockchain.find_transaction(&[0u8; 32]), None);
+
+        let counter = blockchain.state.get_contract_storage(&contract_id, b"count").unwrap().unwrap();
+        assert_eq!(u64::from_le_bytes(counter.try_into().unwrap()), 2);
+        assert_eq!(blockchain.state.get_contract(&contract_id).unwrap().unwrap().balance, 20);
+        let user_account = blockchain.state.get_account(&user.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(user_account.balance, 980);
+    }
+
+    #[test]
+    fn test_contract_out_of_fuel_rolls_back() {
+        let (_dir, mut blockchain, authority, [user]) = setup_chain([1000]);
+
+        let looping_wat = r#"
+            (module
+              (import "env" "storage_write" (func $write (param i32 i32 i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "key")
+              (func (export "call") (result i32)
+                (call $write (i32.const 0) (i32.const 3) (i32.const 0) (i32.const 3))
+                (loop $forever (br $forever))
+                (i32.const 0)))
+        "#;
+        let deploy = deploy_tx(&user, looping_wat, 0);
+        let contract_id = deploy.derived_id();
+        let block = blockchain.create_block(&authority, vec![deploy]);
+        assert!(blockchain.add_block(block));
+
+        // Transaksi tetap masuk blok dan membayar biaya; hanya efek kontraknya yang hilang.
+        let data = TransactionData::CallContract { contract_id, input: vec![], amount: 50, fuel_limit: 10_000 };
+        let call = sign_test_tx_with_fee(&user, data, 1, 5);
+        let block = blockchain.create_block(&authority, vec![call]);
+        assert!(blockchain.add_block(block));
+
+        assert!(blockchain.state.get_contract_storage(&contract_id, b"key").unwrap().is_none());
+        assert_eq!(blockchain.state.get_contract(&contract_id).unwrap().unwrap().balance, 0);
+        let user_account = blockchain.state.get_account(&user.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(user_account.balance, 995);
+        assert_eq!(user_account.nonce, 2);
+    }
+
+    #[test]
+    fn test_contract_memory_is_limited() {
+        let (_dir, mut blockchain, authority, [user]) = setup_chain([1000]);
+
+        // 1000 halaman (~64 MiB) melebihi batas memory, jadi kontrak tidak pernah tersimpan.
+        let huge_memory_wat = r#"(module (memory (export "memory") 1000))"#;
+        let deploy = deploy_tx(&user, huge_memory_wat, 0);
+        let huge_id = deploy.derived_id();
+        let growing_wat = r#"
+            (module
+              (import "env" "storage_write" (func $write (param i32 i32 i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "key")
+              (func (export "call") (result i32)
+                (if (i32.ne (memory.grow (i32.const 1000)) (i32.const -1))
+                  (then (call $write (i32.const 0) (i32.const 3) (i32.const 0) (i32.const 3))))
+                (i32.const 0)))
+        "#;
+        let deploy_growing = deploy_tx(&user, growing_wat, 1);
+        let growing_id = deploy_growing.derived_id();
+        let data = TransactionData::CallContract { contract_id: growing_id, input: vec![], amount: 0, fuel_limit: 100_000 };
+        let call = sign_test_tx(&user, data, 2);
+        let block = blockchain.create_block(&authority, vec![deploy, deploy_growing, call]);
+        assert!(blockchain.add_block(block));
+
+        assert!(blockchain.state.get_contract(&huge_id).unwrap().is_none());
+        assert!(blockchain.state.get_contract_storage(&growing_id, b"key").unwrap().is_none());
+        assert_eq!(blockchain.state.get_account(&user.public_key_bytes()).unwrap().unwrap().nonce, 3);
+    }
+
+    #[test]
+    fn test_asset_create_mint_transfer_burn() {
+        let (_dir, mut blockchain, authority, [issuer, holder]) = setup_chain([1000, 1000]);
+
+        let create = sign_test_tx(&issuer, TransactionData::CreateAsset { symbol: "GOLD".to_string(), decimals: 2, max_supply: 1_000 }, 0);
+        let asset_id = create.derived_id();
+        let mint = sign_test_tx(&issuer, TransactionData::MintAsset { asset_id, recipient: holder.public_key_bytes(), amount: 600 }, 1);
+        let block = blockchain.create_block(&authority, vec![create, mint]);
+        assert!(blockchain.add_block(block));
+
+        let over_mint = sign_test_tx(&issuer, TransactionData::MintAsset { asset_id, recipient: holder.public_key_bytes(), amount: 401 }, 2);
+        let block = blockchain.create_block(&authority, vec![over_mint]);
+        assert!(!blockchain.add_block(block));
+
+        let receiver = KeyPair::new().public_key_bytes();
+        let transfer = sign_test_tx(&holder, TransactionData::TransferAsset { asset_id, recipient: receiver, amount: 250 }, 0);
+        let burn = sign_test_tx(&holder, TransactionData::BurnAsset { asset_id, amount: 100 }, 1);
+        let block = blockchain.create_block(&authority, vec![transfer, burn]);
+        assert!(blockchain.add_block(block));
+
+        assert_eq!(blockchain.state.get_asset_balance(&asset_id, &holder.public_key_bytes()).unwrap(), 250);
+        assert_eq!(blockchain.state.get_asset_balance(&asset_id, &receiver).unwrap(), 250);
+        let asset = blockchain.state.get_asset(&asset_id).unwrap().unwrap();
+        assert_eq!(asset.total_supply, 500);
+        assert_eq!(asset.symbol, "GOLD");
+    }
+
+    #[test]
+    fn test_nft_mint_transfer_burn_tracks_owner_and_history() {
+        let (_dir, mut blockchain, authority, [alice, bob]) = setup_chain([1000, 1000]);
+
+        let mint = sign_test_tx(&alice, TransactionData::MintNft { metadata_hash: [7; 32] }, 0);
+        let nft_id = mint.derived_id();
+        let transfer = sign_test_tx(&alice, TransactionData::TransferNft { nft_id, recipient: bob.public_key_bytes() }, 1);
+        let block = blockchain.create_block(&authority, vec![mint, transfer]);
+        assert!(blockchain.add_block(block));
+
+        assert!(blockchain.state.get_nfts_by_owner(&alice.public_key_bytes()).unwrap().is_empty());
+        assert_eq!(blockchain.state.get_nfts_by_owner(&bob.public_key_bytes()).unwrap(), vec![nft_id]);
+
+        let stolen = sign_test_tx(&alice, TransactionData::TransferNft { nft_id, recipient: alice.public_key_bytes() }, 2);
+        let block = blockchain.create_block(&authority, vec![stolen]);
+        assert!(!blockchain.add_block(block));
+
+        let burn = sign_test_tx(&bob, TransactionData::BurnNft { nft_id }, 0);
+        let block = blockchain.create_block(&authority, vec![burn]);
+        assert!(blockchain.add_block(block));
+
+        assert!(blockchain.state.get_nfts_by_owner(&bob.public_key_bytes()).unwrap().is_empty());
+        let nft = blockchain.state.get_nft(&nft_id).unwrap().unwrap();
+        assert!(nft.burned);
+        assert_eq!(nft.metadata_hash, [7; 32]);
+        assert_eq!(nft.history.len(), 3);
+        assert_eq!(nft.history[2].kind, NftEventKind::Burned);
+        assert_eq!(nft.history[2].block_height, 2);
+    }
+
+    #[test]
+    fn test_name_register_transfer_and_resolve() {
+        let (_dir, mut blockchain, authority, [alice, bob, payer]) = setup_chain([1000, 1000, 1000]);
+
+        let register = sign_test_tx(&alice, TransactionData::RegisterName { name: "alice.evc".to_string() }, 0);
+        let pay = sign_test_tx(&payer, TransactionData::TransferToName { name: "alice.evc".to_string(), amount: 40 }, 0);
+        let block = blockchain.create_block(&authority, vec![register, pay]);
+        assert!(blockchain.add_block(block));
+
+        let record = blockchain.state.get_name("alice.evc").unwrap().unwrap();
+        assert_eq!(record.expiry_height, 1 + NAME_REGISTRATION_PERIOD);
+        let alice_account = blockchain.state.get_account(&alice.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(alice_account.balance, 1000 - NAME_REGISTRATION_FEE + 40);
+
+        let squat = sign_test_tx(&bob, TransactionData::RegisterName { name: "alice.evc".to_string() }, 0);
+        let block = blockchain.create_block(&authority, vec![squat]);
+        assert!(!blockchain.add_block(block));
+
+        let handover = sign_test_tx(&alice, TransactionData::TransferName { name: "alice.evc".to_string(), new_owner: bob.public_key_bytes() }, 1);
+        let pay_again = sign_test_tx(&payer, TransactionData::TransferToName { name: "alice.evc".to_string(), amount: 60 }, 1);
+        let block = blockchain.create_block(&authority, vec![handover, pay_again]);
+        assert!(blockchain.add_block(block));
+
+        assert_eq!(blockchain.state.resolve_name("alice.evc", 3).unwrap(), Some(bob.public_key_bytes()));
+        let bob_account = blockchain.state.get_account(&bob.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(bob_account.balance, 1060);
+
+        let bad_name = sign_test_tx(&bob, TransactionData::RegisterName { name: "X!.evc".to_string() }, 0);
+        let block = blockchain.create_block(&authority, vec![bad_name]);
+        assert!(!blockchain.add_block(block));
+    }
+
+    #[test]
+    fn test_transfer_and_stake_reject_overflow() {
+        let (_dir, mut blockchain, authority, [sender]) = setup_chain([1000]);
+        let recipient = KeyPair::new().public_key_bytes();
+
+        let overflowing = [
+            sign_test_tx_with_fee(&sender, TransactionData::Transfer { recipient, amount: 2 }, 0, u64::MAX - 1),
+            sign_test_tx_with_fee(&sender, TransactionData::Stake { amount: u64::MAX }, 0, 1),
+        ];
+        for tx in overflowing {
+            let block = blockchain.create_block(&authority, vec![tx]);
+            assert!(!blockchain.add_block(block));
+        }
+        let account = blockchain.state.get_account(&sender.public_key_bytes()).unwrap().unwrap();
+        assert_eq!((account.balance, account.staked_amount, account.nonce), (1000, 0, 0));
+        assert!(blockchain.state.get_account(&recipient).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_name_fees_reject_overflow() {
+        let (_dir, mut blockchain, authority, [alice, payer]) = setup_chain([1000, 1000]);
+
+        let register = sign_test_tx(&alice, TransactionData::RegisterName { name: "alice.evc".to_string() }, 0);
+        let block = blockchain.create_block(&authority, vec![register]);
+        assert!(blockchain.add_block(block));
+
+        let overflowing = [
+            sign_test_tx_with_fee(&alice, TransactionData::RegisterName { name: "bob.evc".to_string() }, 1, u64::MAX),
+            sign_test_tx_with_fee(&alice, TransactionData::RenewName { name: "alice.evc".to_string() }, 1, u64::MAX),
+            sign_test_tx_with_fee(&payer, TransactionData::TransferToName { name: "alice.evc".to_string(), amount: 1 }, 0, u64::MAX),
+        ];
+        for tx in overflowing {
+            let block = blockchain.create_block(&authority, vec![tx]);
+            assert!(!blockchain.add_block(block));
+        }
+        let alice_account = blockchain.state.get_account(&alice.public_key_bytes()).unwrap().unwrap();
+        assert_eq!(alice_account.balance, 1000 - NAME_REGISTRATION_FEE);
+        assert!(blockchain.state.get_name("bob.evc").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_governance_proposal_activates_with_stake_majority() {
+        let (_dir, mut blockchain, authority, [big_staker, small_staker]) = setup_chain([1000, 1000]);
+
+        let stake_big = sign_test_tx(&big_staker, TransactionData::Stake { amount: 600 }, 0);
+        let stake_small = sign_test_tx(&small_staker, TransactionData::Stake { amount: 200 }, 0);
+        let propose = sign_test_tx(&small_staker, TransactionData::Propose { change: ParamChange::MaxBlockTransactions(1), activation_height: 12 }, 1);
+        let proposal_id = propose.derived_id();
+        let block = blockchain.create_block(&authority, vec![stake_big, stake_small, propose]);
+        assert!(blockchain.add_block(block));
+
+        let vote_yes = sign_test_tx(&big_staker, TransactionData::Vote { proposal_id, approve: true }, 1);
+        let vote_no = sign_test_tx(&small_staker, TransactionData::Vote { proposal_id, approve: false }, 2);
+        let block = blockchain.create_block(&authority, vec![vote_yes, vote_no]);
+        assert!(blockchain.add_block(block));
+
+        let double_vote = sign_test_tx(&big_staker, TransactionData::Vote { proposal_id, approve: true }, 2);
+        let block = blockchain.create_block(&authority, vec![double_vote]);
+        assert!(!blockchain.add_block(block));
+
+        let proposal = blockchain.state.get_proposal(&proposal_id).unwrap().unwrap();
+        assert_eq!((proposal.yes_stake, proposal.no_stake), (600, 200));
+        assert_eq!(proposal.status, ProposalStatus::Voting);
+
+        add_empty_blocks(&mut blockchain, &authority, 10);
+        assert_eq!(blockchain.chain.last().unwrap().index, 12);
+        assert_eq!(blockchain.state.get_proposal(&proposal_id).unwrap().unwrap().status, ProposalStatus::Activated);
+        assert_eq!(blockchain.state.get_params().unwrap().max_block_transactions, 1);
+
+        let receiver = KeyPair::new().public_key_bytes();
+        let two_txs = vec![
+            create_test_tx(&big_staker, receiver, 1, 2),
+            create_test_tx(&big_staker, receiver, 1, 3),
+        ];
+        let block = blockchain.create_block(&authority, two_txs);
+        assert!(!blockchain.add_block(block));
+    }
+
+    #[test]
+    fn test_governance_requires_quorum_of_total_stake() {
+        let (_dir, mut blockchain, authority, [whale, attacker]) = setup_chain([1000, 1000]);
+
+        // Penyerang dengan 1 unit stake mengusulkan dirinya sebagai satu-satunya otoritas
+        // dan tidak ada yang menolak; suara setuju 1 dari total 901 tidak cukup.
+        let stake_whale = sign_test_tx(&whale, TransactionData::Stake { amount: 900 }, 0);
+        let stake_attacker = sign_test_tx(&attacker, TransactionData::Stake { amount: 1 }, 0);
+        let change = ParamChange::AddAuthority(attacker.public_key_bytes());
+        let propose = sign_test_tx(&attacker, TransactionData::Propose { change, activation_height: 11 }, 1);
+        let proposal_id = propose.derived_id();
+        let vote = sign_test_tx(&attacker, TransactionData::Vote { proposal_id, approve: true }, 2);
+        let block = blockchain.create_block(&authority, vec![stake_whale, stake_attacker, propose, vote]);
+        assert!(blockchain.add_block(block));
+        assert_eq!(blockchain.state.get_total_stake().unwrap(), 901);
+
+        add_empty_blocks(&mut blockchain, &authority, 10);
+        assert_eq!(blockchain.state.get_proposal(&proposal_id).unwrap().unwrap().status, ProposalStatus::Rejected);
+        assert!(blockchain.state.get_params().unwrap().authorities.is_empty());
+    }
+
+    #[test]
+    fn test_governance_cannot_remove_last_authority() {
+        let (_dir, mut blockchain, authority, [staker]) = setup_chain([1000]);
+        blockchain.state.set_params(&ChainParams {
+            authorities: vec![authority.public_key_bytes().to_vec()],
+            ..ChainParams::default()
+        }).unwrap();
+
+        let stake = sign_test_tx(&staker, TransactionData::Stake { amount: 100 }, 0);
+        let change = ParamChange::RemoveAuthority(authority.public_key_bytes());
+        let propose = sign_test_tx(&staker, TransactionData::Propose { change, activation_height: 11 }, 1);
+        let block = blockchain.create_block(&authority, vec![stake.clone(), propose]);
+        assert!(!blockchain.add_block(block));
+        let block = blockchain.create_block(&authority, vec![stake]);
+        assert!(blockchain.add_block(block));
+    }
+
+    #[test]
+    fn test_governance_authority_set_rejects_other_signers() {
+        let (_dir, mut blockchain, authority, [staker]) = setup_chain([1000]);
+        let outsider = KeyPair::new();
+
+        let stake = sign_test_tx(&staker, TransactionData::Stake { amount: 100 }, 0);
+        let change = ParamChange::AddAuthority(authority.public_key_bytes());
+        let propose = sign_test_tx(&staker, TransactionData::Propose { change, activation_height: 11 }, 1);
+        let proposal_id = propose.derived_id();
+        let vote = sign_test_tx(&staker, TransactionData::Vote { proposal_id, approve: true }, 2);
+        let block = blockchain.create_block(&authority, vec![stake, propose, vote]);
+        assert!(blockchain.add_block(block));
+        add_empty_blocks(&mut blockchain, &outsider, 9);
+
+        let block = blockchain.create_block(&outsider, vec![]);
+        assert!(!blockchain.add_block(block));
+        let block = blockchain.create_block(&authority, vec![]);
+        assert!(blockchain.add_block(block));
+    }
+
+    #[test]
+    fn test_rotate_key_switches_signing_key() {
+        let (_dir, mut blockchain, authority, [old_key]) = setup_chain([1000]);
+        let new_key = KeyPair::new();
+        let address = old_key.public_key_bytes();
+        let receiver = KeyPair::new().public_key_bytes();
+
+        let rotate = sign_test_tx(&old_key, TransactionData::RotateKey { new_key: new_key.public_key() }, 0);
+        let signed_with_new = sign_test_tx_as(address, &new_key, TransactionData::Transfer { recipient: receiver, amount: 10 }, 1);
+        let block = blockchain.create_block(&authority, vec![rotate, signed_with_new]);
+        assert!(blockchain.add_block(block));
+
+        let account = blockchain.state.get_account(&address).unwrap().unwrap();
+        assert_eq!(account.signing_key, Some(new_key.public_key()));
+        assert_eq!(blockchain.state.signing_key(&address).unwrap(), Some(new_key.public_key()));
+
+        let signed_with_old = create_test_tx(&old_key, receiver, 10, 2);
+        let block = blockchain.create_block(&authority, vec![signed_with_old]);
+        assert!(!blockchain.add_block(block));
+    }
+
+    #[test]
+    fn test_hybrid_key_requires_both_signatures() {
+        let (_dir, mut blockchain, authority, [legacy_key]) = setup_chain([1000]);
+        let hybrid_key = KeyPair::generate(SignatureScheme::HybridDilithium2Ed25519);
+        let address = legacy_key.public_key_bytes();
+        let receiver = KeyPair::new().public_key_bytes();
+
+        let upgrade = sign_test_tx(&legacy_key, TransactionData::RotateKey { new_key: hybrid_key.public_key() }, 0);
+        let hybrid_tx = sign_test_tx_as(address, &hybrid_key, TransactionData::Transfer { recipient: receiver, amount: 10 }, 1);
+        let block = blockchain.create_block(&authority, vec![upgrade, hybrid_tx]);
+        assert!(blockchain.add_block(block));
+
+        // Hanya komponen Dilithium yang valid: ditolak.
+        let mut partial = sign_test_tx_as(address, &hybrid_key, TransactionData::Transfer { recipient: receiver, amount: 10 }, 2);
+        let mut envelope = crypto::HybridSignature::from_bytes(&partial.signature).unwrap();
+        envelope.ed25519 = KeyPair::generate(SignatureScheme::Ed25519).sign(&partial.message_hash());
+        partial.signature = envelope.to_bytes();
+        assert!(!partial.verify(Some(&hybrid_key.public_key())));
+
+        let dilithium_only = create_test_tx(&legacy_key, receiver, 10, 2);
+        let block = blockchain.create_block(&authority, vec![dilithium_only]);
+        assert!(!blockchain.add_block(block));
+        assert_eq!(blockchain.state.get_account(&receiver).unwrap().unwrap().balance, 10);
+    }
+
+    #[test]
+    fn test_parallel_and_sequential_verification_agree() {
+        let authority = KeyPair::new();
+        let user = KeyPair::new();
+        let receiver = KeyPair::new().public_key_bytes();
+        let mut transactions: Vec<Transaction> = (0..8).map(|nonce| create_test_tx(&user, receiver, 1, nonce)).collect();
+        transactions[5].signature = KeyPair::new().sign(&transactions[5].message_hash());
+
+        for parallel in [false, true] {
+            let dir = tempdir().unwrap();
+            let mut blockchain = Blockchain::new(dir.path().to_str().unwrap());
+            blockchain.parallel_verification = parallel;
+            blockchain.state.set_account(&user.public_key_bytes(), &Account::new(1000)).unwrap();
+            let mut params = blockchain.state.get_params().unwrap();
+            params.max_block_transactions = 8;
+            blockchain.state.set_params(&params).unwrap();
+
+            let block = blockchain.create_block(&authority, transactions.clone());
+            assert!(!blockchain.add_block(block));
+            let block = blockchain.create_block(&authority, transactions[..5].to_vec());
+            assert!(blockchain.add_block(block));
+            assert_eq!(blockchain.state.get_account(&receiver).unwrap().unwrap().balance, 5);
+        }
+    }
+
+    #[test]
+    fn test_signature_cache_is_bound_to_signing_key() {
+        let user = KeyPair::new();
+        let rotated = KeyPair::new();
+        let cache = SignatureCache::new(16);
+        let tx = create_test_tx(&user, KeyPair::new().public_key_bytes(), 1, 0);
+
+        assert!(tx.verify_cached(None, &cache));
+        assert_eq!(cache.len(), 1);
+        assert!(tx.verify_cached(None, &cache));
+        // Setelah rotasi kunci, entri cache untuk kunci lama tidak boleh dipakai.
+        assert!(!tx.verify_cached(Some(&rotated.public_key()), &cache));
+    }
+
+    #[test]
+    fn test_legacy_account_encoding_still_readable() {
+        let dir = tempdir().unwrap();
+        let blockchain = Blockchain::new(dir.path().to_str().unwrap());
+        let address = KeyPair::new().public_key_bytes();
+        let legacy = bincode::serialize(&(700u64, 20u64, 3u64)).unwrap();
+        blockchain.state.db.put(address, legacy).unwrap();
+
+        let account = blockchain.state.get_account(&address).unwrap().unwrap();
+        assert_eq!((account.balance, account.staked_amount, account.nonce), (700, 20, 3));
+        assert!(account.signing_key.is_none());
+    }
+
+    #[test]
+    fn test_block_state_rollback_restores_only_failed_transaction() {
+        let address = KeyPair::new().public_key_bytes();
+        let mut block_state = BlockState::default();
+        block_state.accounts.insert(address, Account::new(100));
+
+        block_state.begin();
+        block_state.accounts.insert(address, Account::new(50));
+        block_state.accounts.insert(address, Account::new(10));
+        block_state.votes.insert(([1; 32], address));
+        block_state.total_stake = Some(5);
+        block_state.rollback();
+        assert_eq!(block_state.accounts.get(&address).unwrap().balance, 100);
+        assert!(!block_state.votes.contains(&([1; 32], address)));
+        assert_eq!(block_state.total_stake, None);
+
+        block_state.begin();
+        block_state.accounts.insert(address, Account::new(70));
+        block_state.release();
+        block_state.rollback();
+        assert_eq!(block_state.accounts.get(&address).unwrap().balance, 70);
+    }
+
+    #[test]
+    fn test_legacy_total_stake_computed_once() {
+        let dir = tempdir().unwrap();
+        let blockchain = Blockchain::new(dir.path().to_str().unwrap());
+        let address = KeyPair::new().public_key_bytes();
+        let legacy = bincode::serialize(&(700u64, 20u64, 3u64)).unwrap();
+        blockchain.state.db.put(address, legacy).unwrap();
+
+        assert_eq!(blockchain.state.get_total_stake().unwrap(), 20);
+        // Nilai yang tersimpan dipakai, bukan hasil pemindaian ulang.
+        blockchain.state.set_account(&address, &Account::new(700)).unwrap();
+        assert_eq!(blockchain.state.get_total_stake().unwrap(), 20);
+    }
+
+    #[test]
+    fn test_ed25519_account_and_authority() {
+        let dir = tempdir().unwrap();
+        let mut blockchain = Blockchain::new(dir.path().to_str().unwrap());
+        let authority = KeyPair::generate(SignatureScheme::Ed25519);
+        let user = KeyPair::generate(SignatureScheme::Ed25519);
+        let receiver = KeyPair::new().public_key_bytes();
+        blockchain.state.set_account(&user.public_key_bytes(), &Account::new(1000)).unwrap();
+
+        let tx = create_test_tx(&user, receiver, 100, 0);
+        assert_eq!(tx.signature.len(), SignatureScheme::Ed25519.signature_size());
+        let block = blockchain.create_block(&authority, vec![tx]);
+        assert!(blockchain.add_block(block));
+        assert_eq!(blockchain.state.get_account(&receiver).unwrap().unwrap().balance, 100);
+
+        let impostor = KeyPair::generate(SignatureScheme::Ed25519);
+        let mut forged = sign_test_tx_as(user.public_key_bytes(), &impostor, TransactionData::Transfer { recipient: receiver, amount: 1 }, 1);
+        assert!(!forged.verify(None));
+        forged.signer = Some(user.public_key());
+        assert!(!forged.verify(None));
+    }
+
+    #[test]
+    fn test_legacy_dilithium2_json_transaction_still_verifies() {
+        let user = KeyPair::new();
+        let tx = create_test_tx(&user, KeyPair::new().public_key_bytes(), 5, 0);
+        let mut json: serde_json::Value = serde_json::to_value(&tx).unwrap();
+        let object = json.as_object_mut().unwrap();
+        object.remove("signer");
+        object.remove("signature_scheme");
+
+        let decoded: Transaction = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.signature_scheme, SignatureScheme::Dilithium2);
+        assert!(decoded.verify(None));
+        assert_eq!(Block::genesis().hash, Block::calculate_hash(&Block::genesis()));
+    }
+
+    #[test]
+    fn test_legacy_dilithium2_bincode_transaction_still_verifies() {
+        let user = KeyPair::new();
+        let mut tx = create_test_tx(&user, KeyPair::new().public_key_bytes(), 5, 0);
+        tx.signer = None;
+        // Layout bincode sebelum `signer`/`signature_scheme` ada.
+        let legacy = bincode::serialize(&(
+            serde_bytes::Bytes::new(&tx.sender),
+            &tx.data,
+            tx.fee,
+            tx.nonce,
+            serde_bytes::Bytes::new(&tx.signature),
+        ))
+        .unwrap();
+        assert!(bincode::deserialize::<Transaction>(&legacy).is_err());
+
+        let decoded = Transaction::from_bincode(&legacy).unwrap();
+        assert_eq!(decoded, tx);
+        assert!(decoded.verify(None));
+        let reencoded = bincode::serialize(&decoded).unwrap();
+        assert_ne!(reencoded, legacy);
+        assert_eq!(Transaction::from_bincode(&reencoded).unwrap(), tx);
+
+        // Jurnal berisi record lama tetap terbaca dan dimigrasi saat ditulis ulang.
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mempool.journal");
+        let mut file = (legacy.len() as u32).to_le_bytes().to_vec();
+        file.extend_from_slice(&legacy);
+        std::fs::write(&path, file).unwrap();
+        let journaled = Journal::read(&path).unwrap();
+        assert_eq!(journaled, vec![tx.clone()]);
+        Journal::rewrite(&path, &journaled).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[4..], reencoded[..]);
+    }
 }
@@ -0,0 +1,291 @@
+// src/contract.rs
+
+use rocksdb::DB;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasmi::core::Trap;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::crypto::PUBLIC_KEY_SIZE;
+use crate::state::{contract_storage_key, Address, ContractId};
+
+pub const MAX_CONTRACT_CODE_SIZE: usize = 256 * 1024;
+pub const MAX_FUEL_PER_TX: u64 = 10_000_000;
+pub const MAX_STORAGE_KEY_SIZE: usize = 64;
+pub const MAX_STORAGE_VALUE_SIZE: usize = 16 * 1024;
+// Batas linear memory per kontrak, berlaku untuk ukuran awal maupun `memory.grow`.
+pub const MAX_CONTRACT_MEMORY_BYTES: usize = 4 * 1024 * 1024;
+
+// Biaya fuel tambahan untuk host function, di luar fuel instruksi WASM.
+const STORAGE_READ_FUEL: u64 = 100;
+const STORAGE_WRITE_FUEL: u64 = 1_000;
+const TRANSFER_FUEL: u64 = 1_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Contract {
+    #[serde(with = "serde_bytes")]
+    pub creator: Address,
+    #[serde(with = "serde_bytes")]
+    pub code: Vec<u8>,
+    pub balance: u64,
+}
+
+// Data yang dapat dibaca kontrak selama satu eksekusi.
+pub struct ExecutionContext {
+    pub contract_id: ContractId,
+    pub caller: Address,
+    pub value: u64,
+    pub balance: u64,
+    pub block_height: u64,
+    pub input: Vec<u8>,
+    pub fuel_limit: u64,
+}
+
+// Hasil eksekusi yang berhasil. Tidak ada yang diterapkan ke state sampai
+// pemanggil menggabungkannya, sehingga eksekusi gagal otomatis di-rollback.
+#[derive(Debug)]
+pub struct ExecutionOutcome {
+    pub storage_writes: HashMap<Vec<u8>, Vec<u8>>,
+    pub transfers: Vec<(Address, u64)>,
+    pub balance: u64,
+    pub fuel_used: u64,
+}
+
+struct Host {
+    db: Arc<DB>,
+    pending_storage: HashMap<Vec<u8>, Vec<u8>>,
+    ctx: ExecutionContext,
+    storage_writes: HashMap<Vec<u8>, Vec<u8>>,
+    transfers: Vec<(Address, u64)>,
+    limits: StoreLimits,
+}
+
+impl Host {
+    fn storage_get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Trap> {
+        if let Some(value) = self.storage_writes.get(key).or_else(|| self.pending_storage.get(key)) {
+            return Ok(Some(value.clone()));
+        }
+        self.db
+            .get(contract_storage_key(&self.ctx.contract_id, key))
+            .map_err(|e| Trap::new(format!("gagal membaca storage kontrak: {}", e)))
+    }
+}
+
+fn memory(caller: &Caller<'_, Host>) -> Result<Memory, Trap> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("kontrak tidak mengekspor memory"))
+}
+
+fn read_memory(caller: &Caller<'_, Host>, ptr: i32, len: i32) -> Result<Vec<u8>, Trap> {
+    let mut buffer = vec![0u8; len as u32 as usize];
+    memory(caller)?
+        .read(caller, ptr as u32 as usize, &mut buffer)
+        .map_err(|e| Trap::new(e.to_string()))?;
+    Ok(buffer)
+}
+
+fn write_memory(caller: &mut Caller<'_, Host>, ptr: i32, data: &[u8]) -> Result<(), Trap> {
+    memory(caller)?
+        .write(caller, ptr as u32 as usize, data)
+        .map_err(|e| Trap::new(e.to_string()))
+}
+
+fn consume_fuel(caller: &mut Caller<'_, Host>, amount: u64) -> Result<(), Trap> {
+    caller
+        .consume_fuel(amount)
+        .map(|_| ())
+        .map_err(|e| Trap::new(e.to_string()))
+}
+
+// Host function yang tersedia untuk kontrak di modul import "env":
+//   input_len() -> i32                       panjang input panggilan
+//   input_read(ptr)                          salin input ke memory kontrak
+//   caller(ptr)                              salin alamat pemanggil (1312 byte)
+//   value() -> i64                           jumlah koin yang dikirim bersama panggilan
+//   balance() -> i64                         saldo kontrak saat ini
+//   block_height() -> i64                    tinggi blok yang sedang diproses
+//   storage_read(kp, kl, vp, vcap) -> i32    panjang nilai, atau -1 jika kunci tidak ada
+//   storage_write(kp, kl, vp, vl)            tulis nilai ke storage kontrak
+//   transfer(addr_ptr, amount) -> i32        kirim saldo kontrak, 0 jika berhasil
+fn linker(engine: &Engine) -> Result<Linker<Host>, String> {
+    let mut linker = Linker::<Host>::new(engine);
+    let map_err = |e: wasmi::errors::LinkerError| e.to_string();
+
+    linker
+        .func_wrap("env", "input_len", |caller: Caller<'_, Host>| -> i32 {
+            caller.data().ctx.input.len() as i32
+        })
+        .map_err(map_err)?;
+    linker
+        .func_wrap("env", "input_read", |mut caller: Caller<'_, Host>, ptr: i32| -> Result<(), Trap> {
+            let input = caller.data().ctx.input.clone();
+            write_memory(&mut caller, ptr, &input)
+        })
+        .map_err(map_err)?;
+    linker
+        .func_wrap("env", "caller", |mut caller: Caller<'_, Host>, ptr: i32| -> Result<(), Trap> {
+            let address = caller.data().ctx.caller;
+            write_memory(&mut caller, ptr, &address)
+        })
+        .map_err(map_err)?;
+    linker
+        .func_wrap("env", "value", |caller: Caller<'_, Host>| -> i64 {
+            caller.data().ctx.value as i64
+        })
+        .map_err(map_err)?;
+    linker
+        .func_wrap("env", "balance", |caller: Caller<'_, Host>| -> i64 {
+            caller.data().ctx.balance as i64
+        })
+        .map_err(map_err)?;
+    linker
+        .func_wrap("env", "block_height", |caller: Caller<'_, Host>| -> i64 {
+            caller.data().ctx.block_height as i64
+        })
+        .map_err(map_err)?;
+    linker
+        .func_wrap(
+            "env",
+            "storage_read",
+            |mut caller: Caller<'_, Host>, key_ptr: i32, key_len: i32, value_ptr: i32, value_cap: i32| -> Result<i32, Trap> {
+                consume_fuel(&mut caller, STORAGE_READ_FUEL)?;
+                if key_len as u32 as usize > MAX_STORAGE_KEY_SIZE {
+                    return Err(Trap::new("kunci storage terlalu panjang"));
+                }
+                let key = read_memory(&caller, key_ptr, key_len)?;
+                match caller.data().storage_get(&key)? {
+                    Some(value) => {
+                        let copied = value.len().min(value_cap as u32 as usize);
+                        write_memory(&mut caller, value_ptr, &value[..copied])?;
+                        Ok(value.len() as i32)
+                    }
+                    None => Ok(-1),
+                }
+            },
+        )
+        .map_err(map_err)?;
+    linker
+        .func_wrap(
+            "env",
+            "storage_write",
+            |mut caller: Caller<'_, Host>, key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32| -> Result<(), Trap> {
+                if key_len as u32 as usize > MAX_STORAGE_KEY_SIZE {
+                    return Err(Trap::new("kunci storage terlalu panjang"));
+                }
+                if value_len as u32 as usize > MAX_STORAGE_VALUE_SIZE {
+                    return Err(Trap::new("nilai storage terlalu besar"));
+                }
+                consume_fuel(&mut caller, STORAGE_WRITE_FUEL + value_len as u32 as u64)?;
+                let key = read_memory(&caller, key_ptr, key_len)?;
+                let value = read_memory(&caller, value_ptr, value_len)?;
+                caller.data_mut().storage_writes.insert(key, value);
+                Ok(())
+            },
+        )
+        .map_err(map_err)?;
+    linker
+        .func_wrap(
+            "env",
+            "transfer",
+            |mut caller: Caller<'_, Host>, address_ptr: i32, amount: i64| -> Result<i32, Trap> {
+                consume_fuel(&mut caller, TRANSFER_FUEL)?;
+                let amount = u64::try_from(amount).map_err(|_| Trap::new("jumlah transfer negatif"))?;
+                let address: Address = read_memory(&caller, address_ptr, PUBLIC_KEY_SIZE as i32)?
+                    .try_into()
+                    .expect("Panjang alamat sudah sesuai");
+                let host = caller.data_mut();
+                if host.ctx.balance < amount {
+                    return Ok(1);
+                }
+                host.ctx.balance -= amount;
+                host.transfers.push((address, amount));
+                Ok(0)
+            },
+        )
+        .map_err(map_err)?;
+
+    Ok(linker)
+}
+
+// Memastikan kode dapat di-parse dan hanya memakai fitur deterministik.
+pub fn validate_code(code: &[u8]) -> Result<(), String> {
+    if code.len() > MAX_CONTRACT_CODE_SIZE {
+        return Err(format!("Kode kontrak terlalu besar ({} byte, maks {})", code.len(), MAX_CONTRACT_CODE_SIZE));
+    }
+    Module::new(&engine(), code)
+        .map(|_| ())
+        .map_err(|e| format!("Kode kontrak tidak valid: {}", e))
+}
+
+fn engine() -> Engine {
+    let mut config = Config::default();
+    // Floating point dimatikan agar eksekusi identik di semua node.
+    config.consume_fuel(true).floats(false);
+    Engine::new(&config)
+}
+
+// Menjalankan fungsi `entry` (tanpa parameter, mengembalikan i32) pada kontrak.
+// Nilai kembali selain 0 dianggap revert. Jika `entry` tidak diekspor dan
+// `required` bernilai false, eksekusi dianggap berhasil tanpa efek.
+pub fn execute(
+    db: Arc<DB>,
+    pending_storage: HashMap<Vec<u8>, Vec<u8>>,
+    code: &[u8],
+    entry: &str,
+    required: bool,
+    ctx: ExecutionContext,
+) -> Result<ExecutionOutcome, String> {
+    let fuel_limit = ctx.fuel_limit.min(MAX_FUEL_PER_TX);
+    let engine = engine();
+    let module = Module::new(&engine, code).map_err(|e| format!("Kode kontrak tidak valid: {}", e))?;
+
+    let mut store = Store::new(
+        &engine,
+        Host {
+            db,
+            pending_storage,
+            ctx,
+            storage_writes: HashMap::new(),
+            transfers: Vec::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_CONTRACT_MEMORY_BYTES)
+                .memories(1)
+                .tables(1)
+                .table_elements(1024)
+                .instances(1)
+                .build(),
+        },
+    );
+    store.limiter(|host| &mut host.limits);
+    store.add_fuel(fuel_limit).map_err(|e| e.to_string())?;
+
+    let instance = linker(&engine)?
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| format!("Gagal menginstansiasi kontrak: {}", e))?;
+
+    match instance.get_typed_func::<(), i32>(&store, entry) {
+        Ok(func) => {
+            let code = func
+                .call(&mut store, ())
+                .map_err(|e| format!("Eksekusi kontrak gagal: {}", e))?;
+            if code != 0 {
+                return Err(format!("Kontrak melakukan revert dengan kode {}", code));
+            }
+        }
+        Err(_) if !required => {}
+        Err(e) => return Err(format!("Kontrak tidak mengekspor fungsi '{}': {}", entry, e)),
+    }
+
+    let fuel_used = store.fuel_consumed().unwrap_or(0);
+    let host = store.into_data();
+    Ok(ExecutionOutcome {
+        storage_writes: host.storage_writes,
+        transfers: host.transfers,
+        balance: host.ctx.balance,
+        fuel_used,
+    })
+}
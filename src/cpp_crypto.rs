@@ -0,0 +1,103 @@
+// src/cpp_crypto.rs
+
+// Wrapper aman untuk backend Ed25519 C++ di cpp_crypto/crypto_api.h.
+// Hanya dikompilasi dengan feature `cpp_crypto`.
+
+use std::os::raw::c_int;
+use std::ptr::NonNull;
+
+pub const PUBLIC_KEY_SIZE: usize = 32;
+pub const PRIVATE_KEY_SIZE: usize = 32;
+pub const SIGNATURE_SIZE: usize = 64;
+
+#[repr(C)]
+struct RawKeyPair {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn create_keypair_from_private(private_key: *const u8) -> *mut RawKeyPair;
+    fn create_keypair() -> *mut RawKeyPair;
+    fn free_keypair(pair: *mut RawKeyPair);
+    fn get_keys_from_pair(pair: *const RawKeyPair, public_key_out: *mut u8, private_key_out: *mut u8);
+    fn sign_message(pair: *const RawKeyPair, message: *const u8, message_len: usize, signature_out: *mut u8) -> c_int;
+    fn verify_signature(public_key: *const u8, message: *const u8, message_len: usize, signature: *const u8) -> c_int;
+}
+
+// Pemilik tunggal `Ed25519KeyPair*` dari C++; memori dibebaskan lewat `free_keypair` saat di-drop.
+pub struct Ed25519KeyPair {
+    raw: NonNull<RawKeyPair>,
+}
+
+// EVP_PKEY hanya dibaca setelah dibuat, sehingga aman dipindah antar thread.
+unsafe impl Send for Ed25519KeyPair {}
+
+impl Ed25519KeyPair {
+    pub fn generate() -> Option<Self> {
+        NonNull::new(unsafe { create_keypair() }).map(|raw| Self { raw })
+    }
+
+    pub fn from_private_key(private_key: &[u8; PRIVATE_KEY_SIZE]) -> Option<Self> {
+        NonNull::new(unsafe { create_keypair_from_private(private_key.as_ptr()) }).map(|raw| Self { raw })
+    }
+
+    // Mengembalikan (public key, private key) mentah.
+    pub fn keys(&self) -> ([u8; PUBLIC_KEY_SIZE], [u8; PRIVATE_KEY_SIZE]) {
+        let mut public_key = [0u8; PUBLIC_KEY_SIZE];
+        let mut private_key = [0u8; PRIVATE_KEY_SIZE];
+        unsafe { get_keys_from_pair(self.raw.as_ptr(), public_key.as_mut_ptr(), private_key.as_mut_ptr()) };
+        (public_key, private_key)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Option<[u8; SIGNATURE_SIZE]> {
+        let mut signature = [0u8; SIGNATURE_SIZE];
+        let ok = unsafe { sign_message(self.raw.as_ptr(), message.as_ptr(), message.len(), signature.as_mut_ptr()) };
+        (ok == 1).then_some(signature)
+    }
+}
+
+impl Drop for Ed25519KeyPair {
+    fn drop(&mut self) {
+        unsafe { free_keypair(self.raw.as_ptr()) };
+    }
+}
+
+// Hasil `-1` (error dari OpenSSL) diperlakukan sama dengan tanda tangan tidak valid.
+pub fn verify(public_key: &[u8; PUBLIC_KEY_SIZE], message: &[u8], signature: &[u8; SIGNATURE_SIZE]) -> bool {
+    unsafe { verify_signature(public_key.as_ptr(), message.as_ptr(), message.len(), signature.as_ptr()) == 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey, Verifier};
+
+    #[test]
+    fn test_ffi_signature_verifies_with_dalek() {
+        let pair = Ed25519KeyPair::generate().unwrap();
+        let (public_key, private_key) = pair.keys();
+        let signature = pair.sign(b"pesan dari C++").unwrap();
+
+        let dalek = SigningKey::from_bytes(&private_key);
+        assert_eq!(dalek.verifying_key().to_bytes(), public_key);
+        // Ed25519 deterministik: kedua backend harus menghasilkan tanda tangan yang sama.
+        assert_eq!(dalek.sign(b"pesan dari C++").to_bytes(), signature);
+        assert!(dalek
+            .verifying_key()
+            .verify(b"pesan dari C++", &ed25519_dalek::Signature::from_bytes(&signature))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_dalek_signature_verifies_with_ffi() {
+        let dalek = SigningKey::generate(&mut rand::rngs::OsRng);
+        let public_key = dalek.verifying_key().to_bytes();
+        let signature = dalek.sign(b"pesan dari Rust").to_bytes();
+
+        assert!(verify(&public_key, b"pesan dari Rust", &signature));
+        assert!(!verify(&public_key, b"pesan lain", &signature));
+
+        let pair = Ed25519KeyPair::from_private_key(&dalek.to_bytes()).unwrap();
+        assert_eq!(pair.keys().0, public_key);
+    }
+}
@@ -1,53 +1,699 @@
 // src/crypto.rs
 
+#[cfg(not(feature = "cpp_crypto"))]
+use ed25519_dalek::{Signer, SigningKey};
+use ed25519_dalek::VerifyingKey;
+use pqcrypto_dilithium::{dilithium2, dilithium3, dilithium5};
 use pqcrypto_traits::sign::{
-    PublicKey as PublicKeyTrait, 
+    PublicKey as PublicKeyTrait,
     SecretKey as SecretKeyTrait,
     DetachedSignature as DetachedSignatureTrait
 };
-use pqcrypto_dilithium::dilithium2::{
-    keypair, detached_sign, verify_detached_signature,
-    PublicKey, SecretKey, DetachedSignature,
-};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use zeroize::{Zeroize, Zeroizing};
 
+// Ukuran Dilithium2. Alamat akun selalu berukuran PUBLIC_KEY_SIZE: untuk kunci
+// Dilithium2 alamatnya adalah kunci itu sendiri (format lama), untuk skema lain
+// alamatnya diturunkan lewat `derive_address`.
 pub const PUBLIC_KEY_SIZE: usize = 1312;
 pub const PRIVATE_KEY_SIZE: usize = 2560;
 pub const SIGNATURE_SIZE: usize = 2420;
 
+const DERIVED_ADDRESS_MAGIC: &[u8; 4] = b"EVA\x01";
+const DERIVED_ADDRESS_LEN: usize = DERIVED_ADDRESS_MAGIC.len() + 1 + 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SignatureScheme {
+    #[default]
+    Dilithium2,
+    Dilithium3,
+    Dilithium5,
+    Ed25519,
+    // Dilithium2 + Ed25519: tanda tangan hanya sah jika keduanya valid.
+    HybridDilithium2Ed25519,
+}
+
+impl SignatureScheme {
+    pub fn tag(self) -> u8 {
+        match self {
+            SignatureScheme::Dilithium2 => 0,
+            SignatureScheme::Dilithium3 => 1,
+            SignatureScheme::Dilithium5 => 2,
+            SignatureScheme::Ed25519 => 3,
+            SignatureScheme::HybridDilithium2Ed25519 => 4,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(SignatureScheme::Dilithium2),
+            1 => Some(SignatureScheme::Dilithium3),
+            2 => Some(SignatureScheme::Dilithium5),
+            3 => Some(SignatureScheme::Ed25519),
+            4 => Some(SignatureScheme::HybridDilithium2Ed25519),
+            _ => None,
+        }
+    }
+
+    pub fn public_key_size(self) -> usize {
+        match self {
+            SignatureScheme::Dilithium2 => dilithium2::public_key_bytes(),
+            SignatureScheme::Dilithium3 => dilithium3::public_key_bytes(),
+            SignatureScheme::Dilithium5 => dilithium5::public_key_bytes(),
+            SignatureScheme::Ed25519 => ed25519_dalek::PUBLIC_KEY_LENGTH,
+            SignatureScheme::HybridDilithium2Ed25519 => HybridPublicKey::SIZE,
+        }
+    }
+
+    pub fn signature_size(self) -> usize {
+        match self {
+            SignatureScheme::Dilithium2 => dilithium2::signature_bytes(),
+            SignatureScheme::Dilithium3 => dilithium3::signature_bytes(),
+            SignatureScheme::Dilithium5 => dilithium5::signature_bytes(),
+            SignatureScheme::Ed25519 => ed25519_dalek::SIGNATURE_LENGTH,
+            SignatureScheme::HybridDilithium2Ed25519 => HybridSignature::SIZE,
+        }
+    }
+
+    pub fn is_valid_public_key(self, public_key_bytes: &[u8]) -> bool {
+        match self {
+            SignatureScheme::Dilithium2 => dilithium2::PublicKey::from_bytes(public_key_bytes).is_ok(),
+            SignatureScheme::Dilithium3 => dilithium3::PublicKey::from_bytes(public_key_bytes).is_ok(),
+            SignatureScheme::Dilithium5 => dilithium5::PublicKey::from_bytes(public_key_bytes).is_ok(),
+            SignatureScheme::Ed25519 => <[u8; 32]>::try_from(public_key_bytes)
+                .ok()
+                .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+                .is_some(),
+            SignatureScheme::HybridDilithium2Ed25519 => HybridPublicKey::from_bytes(public_key_bytes).is_some_and(|key| {
+                SignatureScheme::Dilithium2.is_valid_public_key(&key.dilithium)
+                    && SignatureScheme::Ed25519.is_valid_public_key(&key.ed25519)
+            }),
+        }
+    }
+
+    pub fn verify(self, public_key_bytes: &[u8], message: &[u8], signature_bytes: &[u8]) -> bool {
+        macro_rules! verify_dilithium {
+            ($scheme:ident) => {{
+                let Ok(pk) = $scheme::PublicKey::from_bytes(public_key_bytes) else { return false };
+                let Ok(sig) = $scheme::DetachedSignature::from_bytes(signature_bytes) else { return false };
+                $scheme::verify_detached_signature(&sig, message, &pk).is_ok()
+            }};
+        }
+
+        match self {
+            SignatureScheme::Dilithium2 => verify_dilithium!(dilithium2),
+            SignatureScheme::Dilithium3 => verify_dilithium!(dilithium3),
+            SignatureScheme::Dilithium5 => verify_dilithium!(dilithium5),
+            SignatureScheme::Ed25519 => {
+                let Ok(pk_bytes) = <[u8; 32]>::try_from(public_key_bytes) else { return false };
+                let Ok(pk) = VerifyingKey::from_bytes(&pk_bytes) else { return false };
+                let Ok(sig) = ed25519_dalek::Signature::from_slice(signature_bytes) else { return false };
+                pk.verify_strict(message, &sig).is_ok()
+            }
+            SignatureScheme::HybridDilithium2Ed25519 => {
+                let Some(pk) = HybridPublicKey::from_bytes(public_key_bytes) else { return false };
+                let Some(sig) = HybridSignature::from_bytes(signature_bytes) else { return false };
+                SignatureScheme::Dilithium2.verify(&pk.dilithium, message, &sig.dilithium)
+                    && SignatureScheme::Ed25519.verify(&pk.ed25519, message, &sig.ed25519)
+            }
+        }
+    }
+}
+
+// Kunci publik hybrid, dikodekan sebagai `dilithium2 (1312 byte) | ed25519 (32 byte)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridPublicKey {
+    pub dilithium: Vec<u8>,
+    pub ed25519: Vec<u8>,
+}
+
+impl HybridPublicKey {
+    pub const SIZE: usize = PUBLIC_KEY_SIZE + ed25519_dalek::PUBLIC_KEY_LENGTH;
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::SIZE {
+            return None;
+        }
+        let (dilithium, ed25519) = bytes.split_at(PUBLIC_KEY_SIZE);
+        Some(Self { dilithium: dilithium.to_vec(), ed25519: ed25519.to_vec() })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.dilithium.as_slice(), self.ed25519.as_slice()].concat()
+    }
+}
+
+// Amplop tanda tangan hybrid di `Transaction::signature`, dikodekan sebagai
+// `dilithium2 (2420 byte) | ed25519 (64 byte)` atas pesan yang sama.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridSignature {
+    pub dilithium: Vec<u8>,
+    pub ed25519: Vec<u8>,
+}
+
+impl HybridSignature {
+    pub const SIZE: usize = SIGNATURE_SIZE + ed25519_dalek::SIGNATURE_LENGTH;
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::SIZE {
+            return None;
+        }
+        let (dilithium, ed25519) = bytes.split_at(SIGNATURE_SIZE);
+        Some(Self { dilithium: dilithium.to_vec(), ed25519: ed25519.to_vec() })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.dilithium.as_slice(), self.ed25519.as_slice()].concat()
+    }
+}
+
+// Kunci publik beserta skemanya, dengan panjang sesuai skema.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TaggedPublicKey {
+    pub scheme: SignatureScheme,
+    #[serde(with = "serde_bytes")]
+    pub bytes: Vec<u8>,
+}
+
+impl TaggedPublicKey {
+    pub fn is_valid(&self) -> bool {
+        self.scheme.is_valid_public_key(&self.bytes)
+    }
+
+    pub fn verify(&self, message: &[u8], signature_scheme: SignatureScheme, signature_bytes: &[u8]) -> bool {
+        signature_scheme == self.scheme && self.scheme.verify(&self.bytes, message, signature_bytes)
+    }
+
+    // Kunci Dilithium2 lama yang tersimpan langsung sebagai alamat.
+    pub fn from_legacy_address(address: &[u8; PUBLIC_KEY_SIZE]) -> Self {
+        Self { scheme: SignatureScheme::Dilithium2, bytes: address.to_vec() }
+    }
+}
+
+// Alamat akun untuk sebuah kunci. Dilithium2 memakai kunci mentah agar alamat
+// yang sudah ada tetap berlaku; skema lain memakai `magic | tag | sha256(kunci)`
+// yang diisi nol sampai PUBLIC_KEY_SIZE.
+pub fn derive_address(key: &TaggedPublicKey) -> [u8; PUBLIC_KEY_SIZE] {
+    let mut address = [0u8; PUBLIC_KEY_SIZE];
+    if key.scheme == SignatureScheme::Dilithium2 && key.bytes.len() == PUBLIC_KEY_SIZE {
+        address.copy_from_slice(&key.bytes);
+        return address;
+    }
+    address[..DERIVED_ADDRESS_MAGIC.len()].copy_from_slice(DERIVED_ADDRESS_MAGIC);
+    address[DERIVED_ADDRESS_MAGIC.len()] = key.scheme.tag();
+    address[DERIVED_ADDRESS_MAGIC.len() + 1..DERIVED_ADDRESS_LEN].copy_from_slice(&Sha256::digest(&key.bytes));
+    address
+}
+
+pub fn is_derived_address(address: &[u8; PUBLIC_KEY_SIZE]) -> bool {
+    address.starts_with(DERIVED_ADDRESS_MAGIC) && address[DERIVED_ADDRESS_LEN..].iter().all(|b| *b == 0)
+}
+
 pub struct KeyPair {
-    pub public_key: PublicKey,
-    pub private_key: SecretKey,
+    pub scheme: SignatureScheme,
+    public_key: Vec<u8>,
+    private_key: Vec<u8>,
 }
 
 impl KeyPair {
     pub fn new() -> Self {
-        let (pk, sk) = keypair();
-        Self { public_key: pk, private_key: sk }
+        Self::generate(SignatureScheme::Dilithium2)
+    }
+
+    pub fn generate(scheme: SignatureScheme) -> Self {
+        let (public_key, private_key) = match scheme {
+            SignatureScheme::Dilithium2 => {
+                let (pk, sk) = dilithium2::keypair();
+                (pk.as_bytes().to_vec(), sk.as_bytes().to_vec())
+            }
+            SignatureScheme::Dilithium3 => {
+                let (pk, sk) = dilithium3::keypair();
+                (pk.as_bytes().to_vec(), sk.as_bytes().to_vec())
+            }
+            SignatureScheme::Dilithium5 => {
+                let (pk, sk) = dilithium5::keypair();
+                (pk.as_bytes().to_vec(), sk.as_bytes().to_vec())
+            }
+            SignatureScheme::Ed25519 => ed25519_generate(),
+            SignatureScheme::HybridDilithium2Ed25519 => {
+                let (pk, sk) = dilithium2::keypair();
+                let (ed_pk, ed_sk) = ed25519_generate();
+                ([pk.as_bytes(), ed_pk.as_slice()].concat(), [sk.as_bytes(), ed_sk.as_slice()].concat())
+            }
+        };
+        Self { scheme, public_key, private_key }
     }
 
-    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_SIZE] {
-        let signature = detached_sign(message, &self.private_key);
-        signature.as_bytes().try_into().expect("Signature length mismatch")
+    pub fn from_bytes(scheme: SignatureScheme, public_key: &[u8], private_key: &[u8]) -> Result<Self, String> {
+        let private_key_valid = match scheme {
+            SignatureScheme::Dilithium2 => dilithium2::SecretKey::from_bytes(private_key).is_ok(),
+            SignatureScheme::Dilithium3 => dilithium3::SecretKey::from_bytes(private_key).is_ok(),
+            SignatureScheme::Dilithium5 => dilithium5::SecretKey::from_bytes(private_key).is_ok(),
+            SignatureScheme::Ed25519 => private_key.len() == ed25519_dalek::SECRET_KEY_LENGTH,
+            SignatureScheme::HybridDilithium2Ed25519 => {
+                private_key.len() == PRIVATE_KEY_SIZE + ed25519_dalek::SECRET_KEY_LENGTH
+            }
+        };
+        if !private_key_valid {
+            return Err(format!("Private key {:?} tidak valid", scheme));
+        }
+        if !scheme.is_valid_public_key(public_key) {
+            return Err(format!("Public key {:?} tidak valid", scheme));
+        }
+        let keypair = Self { scheme, public_key: public_key.to_vec(), private_key: private_key.to_vec() };
+        if !keypair.public_key_matches() {
+            return Err(format!("Public key {:?} bukan pasangan private key", scheme));
+        }
+        Ok(keypair)
     }
 
+    // Dilithium2 dan Ed25519 menurunkan ulang public key dari private key; Dilithium3/5
+    // tidak punya turunan itu di sini, jadi dicek dengan menandatangani pesan uji.
+    fn public_key_matches(&self) -> bool {
+        let derived = |scheme: SignatureScheme, private_key: &[u8]| match scheme {
+            SignatureScheme::Dilithium2 => crate::dilithium::public_key_from_secret_key(private_key),
+            SignatureScheme::Ed25519 => private_key.try_into().ok().map(|seed| ed25519_from_seed(seed).0),
+            _ => None,
+        };
+        match self.scheme {
+            SignatureScheme::Dilithium2 | SignatureScheme::Ed25519 => {
+                derived(self.scheme, &self.private_key).as_deref() == Some(self.public_key.as_slice())
+            }
+            SignatureScheme::HybridDilithium2Ed25519 => {
+                let (dilithium_sk, ed25519_sk) = self.private_key.split_at(PRIVATE_KEY_SIZE);
+                let (dilithium_pk, ed25519_pk) = self.public_key.split_at(PUBLIC_KEY_SIZE);
+                derived(SignatureScheme::Dilithium2, dilithium_sk).as_deref() == Some(dilithium_pk)
+                    && derived(SignatureScheme::Ed25519, ed25519_sk).as_deref() == Some(ed25519_pk)
+            }
+            SignatureScheme::Dilithium3 | SignatureScheme::Dilithium5 => {
+                let message = b"evice:keypair-check";
+                self.public_key().verify(message, self.scheme, &self.sign(message))
+            }
+        }
+    }
+
+    // Keypair deterministik dari seed 32 byte (lihat `mnemonic::derive_account_seed`).
+    // Dilithium3/5 belum didukung karena keygen berbasis seed hanya tersedia untuk Dilithium2.
+    pub fn from_seed(scheme: SignatureScheme, seed: &[u8; 32]) -> Result<Self, String> {
+        let (public_key, private_key) = match scheme {
+            SignatureScheme::Dilithium2 => crate::dilithium::keypair_from_seed(seed),
+            SignatureScheme::Ed25519 => ed25519_from_seed(seed),
+            SignatureScheme::HybridDilithium2Ed25519 => {
+                let dilithium_seed: [u8; 32] = Sha256::new().chain_update(b"dilithium").chain_update(seed).finalize().into();
+                let ed25519_seed: [u8; 32] = Sha256::new().chain_update(b"ed25519").chain_update(seed).finalize().into();
+                let (pk, sk) = crate::dilithium::keypair_from_seed(&dilithium_seed);
+                let (ed_pk, ed_sk) = ed25519_from_seed(&ed25519_seed);
+                ([pk, ed_pk].concat(), [sk, ed_sk].concat())
+            }
+            SignatureScheme::Dilithium3 | SignatureScheme::Dilithium5 => {
+                return Err(format!("Pembuatan kunci dari seed belum didukung untuk {:?}", scheme));
+            }
+        };
+        Ok(Self { scheme, public_key, private_key })
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self.scheme {
+            SignatureScheme::Dilithium2 => {
+                let sk = dilithium2::SecretKey::from_bytes(&self.private_key).expect("Secret key length mismatch");
+                dilithium2::detached_sign(message, &sk).as_bytes().to_vec()
+            }
+            SignatureScheme::Dilithium3 => {
+                let sk = dilithium3::SecretKey::from_bytes(&self.private_key).expect("Secret key length mismatch");
+                dilithium3::detached_sign(message, &sk).as_bytes().to_vec()
+            }
+            SignatureScheme::Dilithium5 => {
+                let sk = dilithium5::SecretKey::from_bytes(&self.private_key).expect("Secret key length mismatch");
+                dilithium5::detached_sign(message, &sk).as_bytes().to_vec()
+            }
+            SignatureScheme::Ed25519 => {
+                let secret: [u8; 32] = self.private_key.as_slice().try_into().expect("Secret key length mismatch");
+                ed25519_sign(&secret, message)
+            }
+            SignatureScheme::HybridDilithium2Ed25519 => {
+                let (dilithium_sk, ed25519_sk) = self.private_key.split_at(PRIVATE_KEY_SIZE);
+                let sk = dilithium2::SecretKey::from_bytes(dilithium_sk).expect("Secret key length mismatch");
+                let secret: [u8; 32] = ed25519_sk.try_into().expect("Secret key length mismatch");
+                HybridSignature {
+                    dilithium: dilithium2::detached_sign(message, &sk).as_bytes().to_vec(),
+                    ed25519: ed25519_sign(&secret, message),
+                }
+                .to_bytes()
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> TaggedPublicKey {
+        TaggedPublicKey { scheme: self.scheme, bytes: self.public_key.clone() }
+    }
+
+    // Alamat akun untuk kunci ini; untuk Dilithium2 sama dengan kunci publik mentah.
     pub fn public_key_bytes(&self) -> [u8; PUBLIC_KEY_SIZE] {
-        self.public_key.as_bytes().try_into().expect("Public key length mismatch")
+        derive_address(&self.public_key())
     }
 
-    pub fn private_key_bytes(&self) -> [u8; PRIVATE_KEY_SIZE] {
-        self.private_key.as_bytes().try_into().expect("Secret key length mismatch")
+    pub fn private_key_bytes(&self) -> Vec<u8> {
+        self.private_key.clone()
     }
 }
 
-pub fn verify(public_key_bytes: &[u8], message: &[u8], signature_bytes: &[u8]) -> bool {
-    let pk = match PublicKey::from_bytes(public_key_bytes) {
-        Ok(pk) => pk,
-        Err(_) => return false,
-    };
-    let sig = match DetachedSignature::from_bytes(signature_bytes) {
-        Ok(sig) => sig,
-        Err(_) => return false,
-    };
-    
-    verify_detached_signature(&sig, message, &pk).is_ok()
-}
\ No newline at end of file
+// Private key Ed25519 adalah seed 32 byte itu sendiri, sehingga hasilnya sama di kedua backend.
+fn ed25519_from_seed(seed: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+    let public_key = ed25519_dalek::SigningKey::from_bytes(seed).verifying_key().to_bytes();
+    (public_key.to_vec(), seed.to_vec())
+}
+
+// Pembuatan kunci dan penandatanganan Ed25519 memakai backend C++ jika feature
+// `cpp_crypto` aktif. Verifikasi tetap memakai ed25519-dalek di semua node agar
+// aturan konsensus (verifikasi strict) tidak bergantung pada backend.
+#[cfg(feature = "cpp_crypto")]
+fn ed25519_generate() -> (Vec<u8>, Vec<u8>) {
+    let pair = crate::cpp_crypto::Ed25519KeyPair::generate().expect("Gagal membuat keypair Ed25519 lewat cpp_crypto");
+    let (public_key, private_key) = pair.keys();
+    (public_key.to_vec(), private_key.to_vec())
+}
+
+#[cfg(not(feature = "cpp_crypto"))]
+fn ed25519_generate() -> (Vec<u8>, Vec<u8>) {
+    let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    (signing_key.verifying_key().to_bytes().to_vec(), signing_key.to_bytes().to_vec())
+}
+
+#[cfg(feature = "cpp_crypto")]
+fn ed25519_sign(secret: &[u8; 32], message: &[u8]) -> Vec<u8> {
+    crate::cpp_crypto::Ed25519KeyPair::from_private_key(secret)
+        .and_then(|pair| pair.sign(message))
+        .expect("Gagal menandatangani lewat cpp_crypto")
+        .to_vec()
+}
+
+#[cfg(not(feature = "cpp_crypto"))]
+fn ed25519_sign(secret: &[u8; 32], message: &[u8]) -> Vec<u8> {
+    SigningKey::from_bytes(secret).sign(message).to_bytes().to_vec()
+}
+
+impl Drop for KeyPair {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+// --- Keystore ---
+//
+// Private key disimpan terenkripsi dalam file JSON: kunci enkripsi diturunkan
+// dari password dengan Argon2id, lalu private key dienkripsi dengan
+// XChaCha20-Poly1305. Skema dan public key ikut diautentikasi (AAD), sehingga
+// header yang diubah membuat dekripsi gagal.
+
+pub const KEYSTORE_VERSION: u32 = 1;
+pub const KEYSTORE_PASSWORD_ENV: &str = "EVICE_KEYSTORE_PASSWORD";
+// Parameter Argon2id bawaan: 64 MiB, 3 iterasi.
+pub const DEFAULT_KDF_MEMORY_KIB: u32 = 64 * 1024;
+pub const DEFAULT_KDF_ITERATIONS: u32 = 3;
+// Batas atas parameter dari file keystore, agar keystore yang diubah tidak bisa
+// membuat node menghabiskan memori atau CPU saat dibuka.
+pub const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+pub const MAX_KDF_ITERATIONS: u32 = 64;
+pub const MAX_KDF_PARALLELISM: u32 = 16;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub salt: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Keystore {
+    pub version: u32,
+    pub scheme: SignatureScheme,
+    pub address: String,
+    pub public_key: String,
+    pub kdf: KdfParams,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+fn keystore_key(password: &str, kdf: &KdfParams) -> Result<Zeroizing<[u8; 32]>, String> {
+    let salt = hex::decode(&kdf.salt).map_err(|e| format!("Salt keystore tidak valid: {}", e))?;
+    if kdf.memory_kib > MAX_KDF_MEMORY_KIB || kdf.iterations > MAX_KDF_ITERATIONS || kdf.parallelism > MAX_KDF_PARALLELISM {
+        return Err(format!(
+            "Parameter KDF melebihi batas (memori {} KiB, {} iterasi, paralelisme {})",
+            kdf.memory_kib, kdf.iterations, kdf.parallelism
+        ));
+    }
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| format!("Parameter KDF tidak valid: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), &salt, key.as_mut())
+        .map_err(|e| format!("Gagal menurunkan kunci keystore: {}", e))?;
+    Ok(key)
+}
+
+fn keystore_aad(scheme: SignatureScheme, public_key: &[u8]) -> Vec<u8> {
+    [&[KEYSTORE_VERSION as u8, scheme.tag()][..], public_key].concat()
+}
+
+impl Keystore {
+    pub fn encrypt(keypair: &KeyPair, password: &str) -> Result<Self, String> {
+        Self::encrypt_with_params(keypair, password, DEFAULT_KDF_MEMORY_KIB, DEFAULT_KDF_ITERATIONS)
+    }
+
+    pub fn encrypt_with_params(keypair: &KeyPair, password: &str, memory_kib: u32, iterations: u32) -> Result<Self, String> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 24];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let kdf = KdfParams { memory_kib, iterations, parallelism: 1, salt: hex::encode(salt) };
+
+        let key = keystore_key(password, &kdf)?;
+        let aad = keystore_aad(keypair.scheme, &keypair.public_key);
+        let ciphertext = XChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt(XNonce::from_slice(&nonce), Payload { msg: &keypair.private_key, aad: &aad })
+            .map_err(|_| "Gagal mengenkripsi private key".to_string())?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            scheme: keypair.scheme,
+            address: hex::encode(keypair.public_key_bytes()),
+            public_key: hex::encode(&keypair.public_key),
+            kdf,
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<KeyPair, String> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(format!("Versi keystore {} tidak didukung", self.version));
+        }
+        let public_key = hex::decode(&self.public_key).map_err(|e| format!("Public key keystore tidak valid: {}", e))?;
+        let nonce = hex::decode(&self.nonce).map_err(|e| format!("Nonce keystore tidak valid: {}", e))?;
+        let ciphertext = hex::decode(&self.ciphertext).map_err(|e| format!("Ciphertext keystore tidak valid: {}", e))?;
+        if nonce.len() != 24 {
+            return Err("Nonce keystore tidak valid".to_string());
+        }
+
+        let key = keystore_key(password, &self.kdf)?;
+        let aad = keystore_aad(self.scheme, &public_key);
+        let private_key = Zeroizing::new(
+            XChaCha20Poly1305::new(key.as_ref().into())
+                .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &aad })
+                .map_err(|_| "Password salah atau keystore rusak".to_string())?,
+        );
+        KeyPair::from_bytes(self.scheme, &public_key, &private_key)
+    }
+
+    // Field `address` tidak ikut diautentikasi, jadi alamat selalu diturunkan dari
+    // `public_key` dan `scheme` yang menjadi AAD ciphertext.
+    pub fn derived_address(&self) -> Result<String, String> {
+        let bytes = hex::decode(&self.public_key).map_err(|e| format!("Public key keystore tidak valid: {}", e))?;
+        Ok(hex::encode(derive_address(&TaggedPublicKey { scheme: self.scheme, bytes })))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("Gagal membaca keystore {}: {}", path.display(), e))?;
+        let keystore: Self = serde_json::from_str(&json)
+            .map_err(|e| format!("Format keystore {} tidak valid: {}", path.display(), e))?;
+        if keystore.address.trim_start_matches("0x") != keystore.derived_address()? {
+            return Err(format!("Alamat keystore {} tidak cocok dengan public key-nya", path.display()));
+        }
+        Ok(keystore)
+    }
+
+    // Menolak menimpa file yang sudah ada agar keystore lama tidak hilang.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        use std::io::Write;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(path)
+            .map_err(|e| format!("Gagal membuat keystore {}: {}", path.display(), e))?;
+        file.write_all(json.as_bytes())
+            .map_err(|e| format!("Gagal menulis keystore {}: {}", path.display(), e))
+    }
+}
+
+// Membuat keypair baru dan menyimpannya terenkripsi di `path`.
+pub fn create_keystore(path: &Path, scheme: SignatureScheme, password: &str) -> Result<KeyPair, String> {
+    let keypair = KeyPair::generate(scheme);
+    Keystore::encrypt(&keypair, password)?.save(path)?;
+    Ok(keypair)
+}
+
+// Menyimpan keypair yang sudah ada (mis. dari mnemonic atau kunci hex lama) ke keystore.
+pub fn import_keystore(path: &Path, keypair: &KeyPair, password: &str) -> Result<(), String> {
+    Keystore::encrypt(keypair, password)?.save(path)
+}
+
+pub fn unlock_keystore(path: &Path, password: &str) -> Result<KeyPair, String> {
+    Keystore::load(path)?.decrypt(password)
+}
+
+// Mengembalikan private key mentah; pemanggil bertanggung jawab menampilkannya dengan hati-hati.
+pub fn export_private_key(path: &Path, password: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    Ok(Zeroizing::new(unlock_keystore(path, password)?.private_key.clone()))
+}
+
+// Password keystore dari variabel lingkungan EVICE_KEYSTORE_PASSWORD, atau ditanyakan di terminal.
+pub fn keystore_password(prompt: &str) -> Result<String, String> {
+    if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV) {
+        return Ok(password);
+    }
+    rpassword::prompt_password(prompt).map_err(|e| format!("Gagal membaca password: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_scheme_signs_and_verifies() {
+        for scheme in [
+            SignatureScheme::Dilithium2,
+            SignatureScheme::Dilithium3,
+            SignatureScheme::Dilithium5,
+            SignatureScheme::Ed25519,
+            SignatureScheme::HybridDilithium2Ed25519,
+        ] {
+            let keypair = KeyPair::generate(scheme);
+            let signature = keypair.sign(b"pesan");
+            assert_eq!(signature.len(), scheme.signature_size());
+            assert_eq!(keypair.public_key().bytes.len(), scheme.public_key_size());
+            assert!(keypair.public_key().verify(b"pesan", scheme, &signature));
+            assert!(!keypair.public_key().verify(b"pesan lain", scheme, &signature));
+        }
+    }
+
+    #[test]
+    fn test_derived_address_only_for_non_legacy_schemes() {
+        let legacy = KeyPair::new();
+        assert_eq!(legacy.public_key_bytes().to_vec(), legacy.public_key().bytes);
+        assert!(!is_derived_address(&legacy.public_key_bytes()));
+
+        let ed25519 = KeyPair::generate(SignatureScheme::Ed25519);
+        assert!(is_derived_address(&ed25519.public_key_bytes()));
+        assert_ne!(ed25519.public_key_bytes(), KeyPair::generate(SignatureScheme::Ed25519).public_key_bytes());
+    }
+
+    #[test]
+    fn test_hybrid_requires_both_signatures() {
+        let hybrid = KeyPair::generate(SignatureScheme::HybridDilithium2Ed25519);
+        let other = KeyPair::generate(SignatureScheme::HybridDilithium2Ed25519);
+        let scheme = SignatureScheme::HybridDilithium2Ed25519;
+        let valid = HybridSignature::from_bytes(&hybrid.sign(b"pesan")).unwrap();
+        let forged = HybridSignature::from_bytes(&other.sign(b"pesan")).unwrap();
+
+        let only_dilithium = HybridSignature { dilithium: valid.dilithium.clone(), ed25519: forged.ed25519.clone() };
+        let only_ed25519 = HybridSignature { dilithium: forged.dilithium, ed25519: valid.ed25519.clone() };
+        assert!(hybrid.public_key().verify(b"pesan", scheme, &valid.to_bytes()));
+        assert!(!hybrid.public_key().verify(b"pesan", scheme, &only_dilithium.to_bytes()));
+        assert!(!hybrid.public_key().verify(b"pesan", scheme, &only_ed25519.to_bytes()));
+
+        // Komponen tunggal tidak dapat dipakai dengan skema lain untuk kunci hybrid.
+        assert!(!hybrid.public_key().verify(b"pesan", SignatureScheme::Ed25519, &valid.ed25519));
+    }
+
+    #[test]
+    fn test_keystore_round_trip_and_wrong_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akun.json");
+        let keypair = KeyPair::new();
+        Keystore::encrypt_with_params(&keypair, "rahasia", 64, 1).unwrap().save(&path).unwrap();
+
+        let unlocked = unlock_keystore(&path, "rahasia").unwrap();
+        assert_eq!(unlocked.public_key_bytes(), keypair.public_key_bytes());
+        assert_eq!(*export_private_key(&path, "rahasia").unwrap(), keypair.private_key_bytes());
+        assert!(unlock_keystore(&path, "salah").is_err());
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&hex::encode(keypair.private_key_bytes())));
+        // File yang sudah ada tidak boleh ditimpa.
+        assert!(Keystore::encrypt_with_params(&keypair, "lain", 64, 1).unwrap().save(&path).is_err());
+    }
+
+    #[test]
+    fn test_keystore_header_is_authenticated() {
+        let keypair = KeyPair::generate(SignatureScheme::Ed25519);
+        let other = KeyPair::generate(SignatureScheme::Ed25519);
+        let mut keystore = Keystore::encrypt_with_params(&keypair, "rahasia", 64, 1).unwrap();
+        keystore.public_key = hex::encode(other.public_key().bytes);
+        assert!(keystore.decrypt("rahasia").is_err());
+    }
+
+    #[test]
+    fn test_keystore_address_matches_public_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akun.json");
+        let keypair = KeyPair::generate(SignatureScheme::Ed25519);
+        let mut keystore = Keystore::encrypt_with_params(&keypair, "rahasia", 64, 1).unwrap();
+        assert_eq!(keystore.derived_address().unwrap(), hex::encode(keypair.public_key_bytes()));
+
+        keystore.address = hex::encode(KeyPair::generate(SignatureScheme::Ed25519).public_key_bytes());
+        keystore.save(&path).unwrap();
+        assert!(Keystore::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_keystore_rejects_excessive_kdf_params() {
+        let keypair = KeyPair::new();
+        let keystore = Keystore::encrypt_with_params(&keypair, "rahasia", 64, 1).unwrap();
+        for kdf in [
+            KdfParams { memory_kib: MAX_KDF_MEMORY_KIB + 1, ..keystore.kdf.clone() },
+            KdfParams { iterations: MAX_KDF_ITERATIONS + 1, ..keystore.kdf.clone() },
+            KdfParams { parallelism: MAX_KDF_PARALLELISM + 1, ..keystore.kdf.clone() },
+        ] {
+            let tampered = Keystore { kdf, ..keystore.clone() };
+            assert!(tampered.decrypt("rahasia").err().unwrap().contains("melebihi batas"));
+        }
+        assert!(keystore.decrypt("rahasia").is_ok());
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_mismatched_public_key() {
+        for scheme in [
+            SignatureScheme::Dilithium2,
+            SignatureScheme::Dilithium3,
+            SignatureScheme::Dilithium5,
+            SignatureScheme::Ed25519,
+            SignatureScheme::HybridDilithium2Ed25519,
+        ] {
+            let keypair = KeyPair::generate(scheme);
+            let other = KeyPair::generate(scheme);
+            let private_key = keypair.private_key_bytes();
+            assert!(KeyPair::from_bytes(scheme, &keypair.public_key().bytes, &private_key).is_ok());
+            assert!(KeyPair::from_bytes(scheme, &other.public_key().bytes, &private_key).is_err());
+        }
+        let seeded = KeyPair::from_seed(SignatureScheme::HybridDilithium2Ed25519, &[3; 32]).unwrap();
+        assert!(KeyPair::from_bytes(seeded.scheme, &seeded.public_key().bytes, &seeded.private_key_bytes()).is_ok());
+    }
+}
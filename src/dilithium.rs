@@ -0,0 +1,374 @@
+// src/dilithium.rs
+
+// Pembuatan keypair Dilithium2 secara deterministik dari seed 32 byte.
+// pqcrypto hanya menyediakan keygen acak, padahal algoritmanya sendiri
+// sepenuhnya ditentukan oleh seed awal. Modul ini mengikuti
+// `crypto_sign_keypair` PQClean (dilithium2/clean) yang dipakai
+// pqcrypto-dilithium 0.5 langkah demi langkah, sehingga kunci yang dihasilkan
+// kompatibel dengan `detached_sign` dan `verify_detached_signature` dari crate itu.
+
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Shake128, Shake256};
+
+const N: usize = 256;
+const Q: i32 = 8380417;
+const QINV: i32 = 58728449;
+const D: u32 = 13;
+const K: usize = 4;
+const L: usize = 4;
+const ETA: i32 = 2;
+
+const SEEDBYTES: usize = 32;
+const CRHBYTES: usize = 64;
+const TRBYTES: usize = 64;
+const POLYT1_PACKEDBYTES: usize = 320;
+const POLYT0_PACKEDBYTES: usize = 416;
+const POLYETA_PACKEDBYTES: usize = 96;
+
+pub const PUBLIC_KEY_BYTES: usize = SEEDBYTES + K * POLYT1_PACKEDBYTES;
+pub const SECRET_KEY_BYTES: usize =
+    2 * SEEDBYTES + TRBYTES + L * POLYETA_PACKEDBYTES + K * POLYETA_PACKEDBYTES + K * POLYT0_PACKEDBYTES;
+
+const ZETAS: [i32; N] = [
+    0, 25847, -2608894, -518909, 237124, -777960, -876248, 466468, 1826347, 2353451, -359251, -2091905, 3119733,
+    -2884855, 3111497, 2680103, 2725464, 1024112, -1079900, 3585928, -549488, -1119584, 2619752, -2108549,
+    -2118186, -3859737, -1399561, -3277672, 1757237, -19422, 4010497, 280005, 2706023, 95776, 3077325, 3530437,
+    -1661693, -3592148, -2537516, 3915439, -3861115, -3043716, 3574422, -2867647, 3539968, -300467, 2348700,
+    -539299, -1699267, -1643818, 3505694, -3821735, 3507263, -2140649, -1600420, 3699596, 811944, 531354, 954230,
+    3881043, 3900724, -2556880, 2071892, -2797779, -3930395, -1528703, -3677745, -3041255, -1452451, 3475950,
+    2176455, -1585221, -1257611, 1939314, -4083598, -1000202, -3190144, -3157330, -3632928, 126922, 3412210,
+    -983419, 2147896, 2715295, -2967645, -3693493, -411027, -2477047, -671102, -1228525, -22981, -1308169,
+    -381987, 1349076, 1852771, -1430430, -3343383, 264944, 508951, 3097992, 44288, -1100098, 904516, 3958618,
+    -3724342, -8578, 1653064, -3249728, 2389356, -210977, 759969, -1316856, 189548, -3553272, 3159746, -1851402,
+    -2409325, -177440, 1315589, 1341330, 1285669, -1584928, -812732, -1439742, -3019102, -3881060, -3628969,
+    3839961, 2091667, 3407706, 2316500, 3817976, -3342478, 2244091, -2446433, -3562462, 266997, 2434439,
+    -1235728, 3513181, -3520352, -3759364, -1197226, -3193378, 900702, 1859098, 909542, 819034, 495491,
+    -1613174, -43260, -522500, -655327, -3122442, 2031748, 3207046, -3556995, -525098, -768622, -3595838,
+    342297, 286988, -2437823, 4108315, 3437287, -3342277, 1735879, 203044, 2842341, 2691481, -2590150, 1265009,
+    4055324, 1247620, 2486353, 1595974, -3767016, 1250494, 2635921, -3548272, -2994039, 1869119, 1903435,
+    -1050970, -1333058, 1237275, -3318210, -1430225, -451100, 1312455, 3306115, -1962642, -1279661, 1917081,
+    -2546312, -1374803, 1500165, 777191, 2235880, 3406031, -542412, -2831860, -1671176, -1846953, -2584293,
+    -3724270, 594136, -3776993, -2013608, 2432395, 2454455, -164721, 1957272, 3369112, 185531, -1207385,
+    -3183426, 162844, 1616392, 3014001, 810149, 1652634, -3694233, -1799107, -3038916, 3523897, 3866901, 269760,
+    2213111, -975884, 1717735, 472078, -426683, 1723600, -1803090, 1910376, -1667432, -1104333, -260646,
+    -3833893, -2939036, -2235985, -420899, -2286327, 183443, -976891, 1612842, -3545687, -554416, 3919660,
+    -48306, -1362209, 3937738, 1400424, -846154, 1976782,
+];
+
+type Poly = [i32; N];
+
+fn montgomery_reduce(a: i64) -> i32 {
+    let t = (a as i32).wrapping_mul(QINV);
+    ((a - t as i64 * Q as i64) >> 32) as i32
+}
+
+fn reduce32(a: i32) -> i32 {
+    let t = (a + (1 << 22)) >> 23;
+    a - t * Q
+}
+
+fn caddq(a: i32) -> i32 {
+    a + ((a >> 31) & Q)
+}
+
+fn ntt(a: &mut Poly) {
+    let mut k = 0;
+    let mut len = 128;
+    while len > 0 {
+        let mut start = 0;
+        while start < N {
+            k += 1;
+            let zeta = ZETAS[k] as i64;
+            for j in start..start + len {
+                let t = montgomery_reduce(zeta * a[j + len] as i64);
+                a[j + len] = a[j] - t;
+                a[j] += t;
+            }
+            start += 2 * len;
+        }
+        len >>= 1;
+    }
+}
+
+fn invntt_tomont(a: &mut Poly) {
+    // mont^2/256
+    const F: i64 = 41978;
+    let mut k = N;
+    let mut len = 1;
+    while len < N {
+        let mut start = 0;
+        while start < N {
+            k -= 1;
+            let zeta = -ZETAS[k] as i64;
+            for j in start..start + len {
+                let t = a[j];
+                a[j] = t + a[j + len];
+                a[j + len] = montgomery_reduce(zeta * (t - a[j + len]) as i64);
+            }
+            start += 2 * len;
+        }
+        len <<= 1;
+    }
+    for coeff in a.iter_mut() {
+        *coeff = montgomery_reduce(F * *coeff as i64);
+    }
+}
+
+// ExpandA: koefisien seragam mod Q dari SHAKE128(rho || nonce).
+fn poly_uniform(rho: &[u8; SEEDBYTES], nonce: u16) -> Poly {
+    let mut xof = Shake128::default();
+    xof.update(rho);
+    xof.update(&nonce.to_le_bytes());
+    let mut reader = xof.finalize_xof();
+
+    let mut poly = [0; N];
+    let mut ctr = 0;
+    let mut buf = [0u8; 3];
+    while ctr < N {
+        reader.read(&mut buf);
+        let t = (buf[0] as u32 | (buf[1] as u32) << 8 | (buf[2] as u32) << 16) & 0x7F_FFFF;
+        if t < Q as u32 {
+            poly[ctr] = t as i32;
+            ctr += 1;
+        }
+    }
+    poly
+}
+
+// ExpandS untuk ETA = 2: koefisien di [-2, 2] dari SHAKE256(rhoprime || nonce).
+fn poly_uniform_eta(rhoprime: &[u8; CRHBYTES], nonce: u16) -> Poly {
+    let mut xof = Shake256::default();
+    xof.update(rhoprime);
+    xof.update(&nonce.to_le_bytes());
+    let mut reader = xof.finalize_xof();
+
+    let mut poly = [0; N];
+    let mut ctr = 0;
+    let mut byte = [0u8; 1];
+    while ctr < N {
+        reader.read(&mut byte);
+        for t in [byte[0] & 0x0F, byte[0] >> 4] {
+            if t < 15 && ctr < N {
+                poly[ctr] = ETA - (t % 5) as i32;
+                ctr += 1;
+            }
+        }
+    }
+    poly
+}
+
+fn power2round(a: i32) -> (i32, i32) {
+    let a1 = (a + (1 << (D - 1)) - 1) >> D;
+    (a1, a - (a1 << D))
+}
+
+fn polyeta_pack(out: &mut [u8], a: &Poly) {
+    for i in 0..N / 8 {
+        let t: [u8; 8] = std::array::from_fn(|j| (ETA - a[8 * i + j]) as u8);
+        out[3 * i] = t[0] | (t[1] << 3) | (t[2] << 6);
+        out[3 * i + 1] = (t[2] >> 2) | (t[3] << 1) | (t[4] << 4) | (t[5] << 7);
+        out[3 * i + 2] = (t[5] >> 1) | (t[6] << 2) | (t[7] << 5);
+    }
+}
+
+fn polyt1_pack(out: &mut [u8], a: &Poly) {
+    for i in 0..N / 4 {
+        let t: [u32; 4] = std::array::from_fn(|j| a[4 * i + j] as u32);
+        out[5 * i] = t[0] as u8;
+        out[5 * i + 1] = ((t[0] >> 8) | (t[1] << 2)) as u8;
+        out[5 * i + 2] = ((t[1] >> 6) | (t[2] << 4)) as u8;
+        out[5 * i + 3] = ((t[2] >> 4) | (t[3] << 6)) as u8;
+        out[5 * i + 4] = (t[3] >> 2) as u8;
+    }
+}
+
+fn polyt0_pack(out: &mut [u8], a: &Poly) {
+    for i in 0..N / 8 {
+        let t: [u32; 8] = std::array::from_fn(|j| ((1 << (D - 1)) - a[8 * i + j]) as u32);
+        out[13 * i] = t[0] as u8;
+        out[13 * i + 1] = ((t[0] >> 8) | (t[1] << 5)) as u8;
+        out[13 * i + 2] = (t[1] >> 3) as u8;
+        out[13 * i + 3] = ((t[1] >> 11) | (t[2] << 2)) as u8;
+        out[13 * i + 4] = ((t[2] >> 6) | (t[3] << 7)) as u8;
+        out[13 * i + 5] = (t[3] >> 1) as u8;
+        out[13 * i + 6] = ((t[3] >> 9) | (t[4] << 4)) as u8;
+        out[13 * i + 7] = (t[4] >> 4) as u8;
+        out[13 * i + 8] = ((t[4] >> 12) | (t[5] << 1)) as u8;
+        out[13 * i + 9] = ((t[5] >> 7) | (t[6] << 6)) as u8;
+        out[13 * i + 10] = (t[6] >> 2) as u8;
+        out[13 * i + 11] = ((t[6] >> 10) | (t[7] << 3)) as u8;
+        out[13 * i + 12] = (t[7] >> 5) as u8;
+    }
+}
+
+fn polyeta_unpack(a: &[u8]) -> Poly {
+    let mut r = [0; N];
+    for i in 0..N / 8 {
+        let t = [
+            a[3 * i],
+            a[3 * i] >> 3,
+            (a[3 * i] >> 6) | (a[3 * i + 1] << 2),
+            a[3 * i + 1] >> 1,
+            a[3 * i + 1] >> 4,
+            (a[3 * i + 1] >> 7) | (a[3 * i + 2] << 1),
+            a[3 * i + 2] >> 2,
+            a[3 * i + 2] >> 5,
+        ];
+        for (j, t) in t.into_iter().enumerate() {
+            r[8 * i + j] = ETA - (t & 7) as i32;
+        }
+    }
+    r
+}
+
+// t = A*s1 + s2 dipecah menjadi (t1, t0); public key = rho || pack(t1).
+fn public_key(rho: &[u8; SEEDBYTES], s1: &[Poly; L], s2: &[Poly; K]) -> (Vec<u8>, [Poly; K]) {
+    let mut s1hat = *s1;
+    s1hat.iter_mut().for_each(ntt);
+
+    let mut t1 = [[0; N]; K];
+    let mut t0 = [[0; N]; K];
+    for i in 0..K {
+        let mut t = [0; N];
+        for (j, s1hat_j) in s1hat.iter().enumerate() {
+            let a = poly_uniform(rho, ((i << 8) + j) as u16);
+            for n in 0..N {
+                t[n] += montgomery_reduce(a[n] as i64 * s1hat_j[n] as i64);
+            }
+        }
+        t.iter_mut().for_each(|c| *c = reduce32(*c));
+        invntt_tomont(&mut t);
+        for n in 0..N {
+            (t1[i][n], t0[i][n]) = power2round(caddq(t[n] + s2[i][n]));
+        }
+    }
+
+    let mut pk = vec![0u8; PUBLIC_KEY_BYTES];
+    pk[..SEEDBYTES].copy_from_slice(rho);
+    for (i, poly) in t1.iter().enumerate() {
+        let offset = SEEDBYTES + i * POLYT1_PACKEDBYTES;
+        polyt1_pack(&mut pk[offset..offset + POLYT1_PACKEDBYTES], poly);
+    }
+    (pk, t0)
+}
+
+// Mengembalikan (public key, secret key) dalam format pqcrypto-dilithium 0.5.
+pub fn keypair_from_seed(seed: &[u8; SEEDBYTES]) -> (Vec<u8>, Vec<u8>) {
+    let mut seedbuf = [0u8; 2 * SEEDBYTES + CRHBYTES];
+    Shake256::default().chain(seed).finalize_xof().read(&mut seedbuf);
+    let rho: [u8; SEEDBYTES] = seedbuf[..SEEDBYTES].try_into().unwrap();
+    let rhoprime: [u8; CRHBYTES] = seedbuf[SEEDBYTES..SEEDBYTES + CRHBYTES].try_into().unwrap();
+    let key = &seedbuf[SEEDBYTES + CRHBYTES..];
+
+    let s1: [Poly; L] = std::array::from_fn(|i| poly_uniform_eta(&rhoprime, i as u16));
+    let s2: [Poly; K] = std::array::from_fn(|i| poly_uniform_eta(&rhoprime, (L + i) as u16));
+    let (pk, t0) = public_key(&rho, &s1, &s2);
+
+    let mut tr = [0u8; TRBYTES];
+    Shake256::default().chain(&pk).finalize_xof().read(&mut tr);
+
+    let mut sk = vec![0u8; SECRET_KEY_BYTES];
+    sk[..SEEDBYTES].copy_from_slice(&rho);
+    sk[SEEDBYTES..2 * SEEDBYTES].copy_from_slice(key);
+    sk[2 * SEEDBYTES..2 * SEEDBYTES + TRBYTES].copy_from_slice(&tr);
+    let mut offset = 2 * SEEDBYTES + TRBYTES;
+    for poly in s1.iter().chain(s2.iter()) {
+        polyeta_pack(&mut sk[offset..offset + POLYETA_PACKEDBYTES], poly);
+        offset += POLYETA_PACKEDBYTES;
+    }
+    for poly in &t0 {
+        polyt0_pack(&mut sk[offset..offset + POLYT0_PACKEDBYTES], poly);
+        offset += POLYT0_PACKEDBYTES;
+    }
+
+    (pk, sk)
+}
+
+// Menghitung ulang public key dari rho, s1 dan s2 di dalam secret key. `None` jika
+// panjang secret key bukan milik Dilithium2.
+pub fn public_key_from_secret_key(sk: &[u8]) -> Option<Vec<u8>> {
+    if sk.len() != SECRET_KEY_BYTES {
+        return None;
+    }
+    let rho: [u8; SEEDBYTES] = sk[..SEEDBYTES].try_into().unwrap();
+    let eta_offset = 2 * SEEDBYTES + TRBYTES;
+    let unpack = |i: usize| {
+        let offset = eta_offset + i * POLYETA_PACKEDBYTES;
+        polyeta_unpack(&sk[offset..offset + POLYETA_PACKEDBYTES])
+    };
+    let s1: [Poly; L] = std::array::from_fn(unpack);
+    let s2: [Poly; K] = std::array::from_fn(|i| unpack(L + i));
+    Some(public_key(&rho, &s1, &s2).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pqcrypto_dilithium::dilithium2;
+    use pqcrypto_traits::sign::{PublicKey as _, SecretKey as _};
+
+    #[test]
+    fn test_seeded_keys_work_with_pqcrypto() {
+        for seed in [[0u8; 32], [7u8; 32], [0xA5u8; 32]] {
+            let (pk_bytes, sk_bytes) = keypair_from_seed(&seed);
+            assert_eq!((pk_bytes.len(), sk_bytes.len()), (dilithium2::public_key_bytes(), dilithium2::secret_key_bytes()));
+            assert_eq!(keypair_from_seed(&seed), (pk_bytes.clone(), sk_bytes.clone()));
+
+            let pk = dilithium2::PublicKey::from_bytes(&pk_bytes).unwrap();
+            let sk = dilithium2::SecretKey::from_bytes(&sk_bytes).unwrap();
+            for message in [&b"pesan"[..], b"", &[0xFF; 100]] {
+                let signature = dilithium2::detached_sign(message, &sk);
+                assert!(dilithium2::verify_detached_signature(&signature, message, &pk).is_ok());
+            }
+        }
+    }
+
+    // Nilai acuan (SHA-256 dari pk dan sk) dari `PQCLEAN_DILITHIUM2_CLEAN_crypto_sign_keypair`
+    // (sumber C yang dibawa pqcrypto-dilithium 0.5) dengan `randombytes` diganti agar
+    // mengembalikan seed yang diberikan.
+    #[test]
+    fn test_seeded_keys_match_reference_implementation() {
+        use sha2::{Digest, Sha256};
+        let vectors: [([u8; SEEDBYTES], &str, &str); 4] = [
+            (
+                [0; SEEDBYTES],
+                "b9f990b967f47e81e753b400fedcedfcca13d3f313fa3dd7f6639469d7061323",
+                "f56d3c7c1fdfcb4e321debd7790cf782c788a35646c8db68cc6d77df18003cef",
+            ),
+            (
+                [0xFF; SEEDBYTES],
+                "76018879df9381172532de4dbd3dd4c4437abf006355708b19bd4e2b99835b13",
+                "ec887506ca533c6fced0c3252aceb57293bceb17a3d02c7dffc008968187c1c2",
+            ),
+            (
+                std::array::from_fn(|i| i as u8),
+                "36844f5d32e0d5a970cc1fbb9168667d368fff43929c2fdbb499af4d08615e85",
+                "13933d74759087cc6b7c0b5e3b342f78370ba486236584215db6c04e450bac25",
+            ),
+            (
+                std::array::from_fn(|i| (i * 37 + 11) as u8),
+                "e7dee50286f83ef6ee8957328fd9222bec86ea50afc687de5b9fbfc9490790d6",
+                "2f479e2ff3ec9427f178e3b0f3bdceca0870149ef1ae34faa0dacfb78d9d8eec",
+            ),
+        ];
+        for (seed, pk_hash, sk_hash) in vectors {
+            let (pk, sk) = keypair_from_seed(&seed);
+            assert_eq!(hex::encode(Sha256::digest(&pk)), pk_hash);
+            assert_eq!(hex::encode(Sha256::digest(&sk)), sk_hash);
+        }
+    }
+
+    // Public key yang diturunkan dari secret key buatan pqcrypto harus sama persis dengan
+    // public key pasangannya.
+    #[test]
+    fn test_public_key_from_pqcrypto_secret_key_is_identical() {
+        for _ in 0..8 {
+            let (pk, sk) = dilithium2::keypair();
+            assert_eq!(public_key_from_secret_key(sk.as_bytes()).unwrap(), pk.as_bytes());
+        }
+        let (_, other_sk) = dilithium2::keypair();
+        let (pk, _) = dilithium2::keypair();
+        assert_ne!(public_key_from_secret_key(other_sk.as_bytes()).unwrap(), pk.as_bytes());
+        assert!(public_key_from_secret_key(&[0u8; 10]).is_none());
+    }
+}
@@ -0,0 +1,115 @@
+// src/journal.rs
+
+use crate::blockchain::Transaction;
+use log::warn;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+// Batas ukuran satu record, jauh di atas transaksi terbesar yang valid (kode kontrak
+// maksimal `MAX_CONTRACT_CODE_SIZE`). Panjang di atas ini berarti file rusak.
+const MAX_RECORD_SIZE: usize = 1024 * 1024;
+
+// Jurnal transaksi mempool: deret record `panjang (u32 LE) | bincode(Transaction)` yang
+// hanya ditambah di akhir. Record yang terpotong di ujung file (node mati saat menulis)
+// diabaikan saat dibaca. Record berlayout lama tetap terbaca (lihat
+// `Transaction::from_bincode`) dan ditulis ulang dengan layout baru saat jurnal dibuka.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    records: usize,
+}
+
+impl Journal {
+    // Membaca seluruh transaksi dari jurnal di `path`. File yang belum ada dianggap kosong.
+    pub fn read(path: &Path) -> io::Result<Vec<Transaction>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut reader = BufReader::new(file);
+        let mut transactions = Vec::new();
+        loop {
+            let mut len = [0u8; 4];
+            match reader.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let len = u32::from_le_bytes(len) as usize;
+            if len > MAX_RECORD_SIZE {
+                warn!(
+                    "MEMPOOL: Panjang record jurnal {} ({} byte) tidak wajar, sisa file diabaikan.",
+                    path.display(),
+                    len
+                );
+                break;
+            }
+            let mut record = vec![0u8; len];
+            if let Err(e) = reader.read_exact(&mut record) {
+                if e.kind() == io::ErrorKind::UnexpectedEof {
+                    warn!("MEMPOOL: Record terakhir jurnal {} terpotong, diabaikan.", path.display());
+                    break;
+                }
+                return Err(e);
+            }
+            match Transaction::from_bincode(&record) {
+                Ok(tx) => transactions.push(tx),
+                Err(e) => warn!("MEMPOOL: Record jurnal rusak diabaikan: {}", e),
+            }
+        }
+        Ok(transactions)
+    }
+
+    // Menulis ulang jurnal hanya berisi `transactions`, lalu membukanya untuk ditambah.
+    // Ditulis ke file sementara lalu di-rename agar jurnal lama tetap utuh jika gagal.
+    pub fn rewrite<'a>(
+        path: &Path,
+        transactions: impl IntoIterator<Item = &'a Transaction>
+    ) -> io::Result<Self> {
+        let tmp_path = path.with_extension("tmp");
+        let mut records = 0;
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            for tx in transactions {
+                write_record(&mut writer, tx)?;
+                records += 1;
+            }
+            writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        }
+        fs::rename(&tmp_path, path)?;
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(Self { path: path.to_path_buf(), file, records })
+    }
+
+    // Menambahkan beberapa transaksi sekaligus dengan satu fsync.
+    pub fn append(&mut self, transactions: &[Transaction]) -> io::Result<()> {
+        let mut writer = BufWriter::new(&mut self.file);
+        for tx in transactions {
+            write_record(&mut writer, tx)?;
+        }
+        writer.into_inner().map_err(|e| e.into_error())?.sync_data()?;
+        self.records += transactions.len();
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Jumlah record di file, termasuk transaksi yang sudah tidak ada di mempool.
+    pub fn records(&self) -> usize {
+        self.records
+    }
+}
+
+fn write_record(writer: &mut impl Write, tx: &Transaction) -> io::Result<()> {
+    let record = bincode::serialize(tx).map_err(io::Error::other)?;
+    if record.len() > MAX_RECORD_SIZE {
+        return Err(io::Error::other(format!("record {} byte melebihi batas jurnal", record.len())));
+    }
+    let len = u32::try_from(record.len()).map_err(io::Error::other)?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&record)
+}
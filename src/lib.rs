@@ -2,8 +2,15 @@
 
 // Deklarasikan semua modul yang akan menjadi bagian dari library Anda
 pub mod blockchain;
+pub mod contract;
+#[cfg(feature = "cpp_crypto")]
+pub mod cpp_crypto;
 pub mod crypto;
+pub mod dilithium;
+pub mod journal;
 pub mod mempool;
+pub mod mnemonic;
 pub mod p2p;
 pub mod rpc;
+pub mod sigcache;
 pub mod state;
\ No newline at end of file
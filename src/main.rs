@@ -3,18 +3,18 @@
 use clap::Parser;
 use evice_blockchain::{
     blockchain::{Blockchain, ChainMessage},
-    crypto, mempool::Mempool, p2p, rpc,
+    crypto,
+    mempool::{Mempool, MempoolConfig},
+    p2p, rpc, state,
 };
-use log::{error, info};
+use log::{error, info, warn};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc;
-use tokio::time::interval;
+use tokio::time::sleep;
 use tracing_log::LogTracer;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
-const MAX_TRANSACTIONS_PER_BLOCK: usize = 10;
-
 #[derive(Parser, Debug)]
 #[clap(version, about, long_about = None)]
 struct Args {
@@ -30,6 +30,26 @@ struct Args {
     bootstrap_node: Option<String>,
     #[clap(long, default_value = "50000")]
     p2p_port: u16,
+    // Direktori tujuan keystore akun bootstrap.
+    #[clap(long, default_value = "./keystore")]
+    keystore_dir: String,
+    // Keystore kunci otoritas; tanpa ini node otoritas memakai kunci sementara.
+    #[clap(long)]
+    authority_keystore: Option<String>,
+    // Kenaikan fee minimum (persen) untuk mengganti transaksi dengan nonce yang sama.
+    #[clap(long, default_value_t = evice_blockchain::mempool::DEFAULT_REPLACEMENT_FEE_BUMP_PERCENT)]
+    replacement_fee_bump_percent: u64,
+    // Batas jumlah dan total ukuran (byte) transaksi di mempool.
+    #[clap(long, default_value_t = evice_blockchain::mempool::DEFAULT_MAX_TRANSACTIONS)]
+    mempool_max_transactions: usize,
+    #[clap(long, default_value_t = evice_blockchain::mempool::DEFAULT_MAX_BYTES)]
+    mempool_max_bytes: usize,
+    // Batas transaksi menunggu per pengirim.
+    #[clap(long, default_value_t = evice_blockchain::mempool::DEFAULT_MAX_PER_SENDER)]
+    mempool_max_per_sender: usize,
+    // File jurnal mempool; tanpa ini transaksi yang menunggu hilang saat node restart.
+    #[clap(long)]
+    mempool_journal: Option<String>,
 }
 
 #[tokio::main]
@@ -49,16 +69,56 @@ async fn main() {
 
     if args.bootstrap {
         info!("Mem-bootstrap state awal dengan akun genesis...");
-        blockchain.lock().unwrap().state.bootstrap_accounts();
-        info!("Bootstrap selesai. Program berhenti.");
+        let result = crypto::keystore_password("Password untuk keystore bootstrap: ").and_then(|password| {
+            blockchain
+                .lock()
+                .unwrap()
+                .state
+                .bootstrap_accounts(std::path::Path::new(&args.keystore_dir), &password)
+        });
+        match result {
+            Ok(()) => info!("Bootstrap selesai. Program berhenti."),
+            Err(e) => error!("Bootstrap gagal: {}", e),
+        }
         return;
     }
 
-    let mempool = Arc::new(Mempool::new());
+    let signature_cache = blockchain.lock().unwrap().signature_cache.clone();
+    let mempool_config = MempoolConfig {
+        replacement_fee_bump_percent: args.replacement_fee_bump_percent,
+        max_transactions: args.mempool_max_transactions,
+        max_bytes: args.mempool_max_bytes,
+        max_per_sender: args.mempool_max_per_sender,
+    };
+    let mempool = Arc::new(Mempool::with_config(signature_cache, mempool_config));
+    blockchain.lock().unwrap().add_observer(mempool.clone());
+    if let Some(path) = &args.mempool_journal {
+        let chain = blockchain.lock().unwrap();
+        if let Err(e) = mempool.open_journal(std::path::Path::new(path), &chain.state) {
+            error!("Gagal membuka jurnal mempool {}: {}", path, e);
+            return;
+        }
+    }
     let (tx_p2p, rx_p2p) = mpsc::channel::<ChainMessage>(100);
 
     if args.is_authority {
-        let authority_keypair = Arc::new(crypto::KeyPair::new());
+        let authority_keypair = match &args.authority_keystore {
+            Some(path) => {
+                match crypto::keystore_password("Password keystore otoritas: ")
+                    .and_then(|password| crypto::unlock_keystore(std::path::Path::new(path), &password))
+                {
+                    Ok(keypair) => Arc::new(keypair),
+                    Err(e) => {
+                        error!("Gagal membuka keystore otoritas: {}", e);
+                        return;
+                    }
+                }
+            }
+            None => {
+                warn!("Tidak ada --authority-keystore, memakai kunci otoritas sementara.");
+                Arc::new(crypto::KeyPair::new())
+            }
+        };
         info!("Menjalankan sebagai NODE OTORITAS.");
         info!(
         "Alamat Otoritas: 0x{}",
@@ -71,23 +131,69 @@ async fn main() {
         let tx_p2p_clone_auth = tx_p2p.clone();
 
         tokio::spawn(async move {
-            let mut tick = interval(Duration::from_secs(10));
             loop {
-                tick.tick().await;
-                let transactions = mempool_clone.get_transactions(MAX_TRANSACTIONS_PER_BLOCK);
+                // Ukuran dan interval blok diatur lewat governance, jadi dibaca ulang setiap putaran.
+                let params = chain_clone.lock().unwrap().state.get_params();
+                let params = match params {
+                    Ok(params) => params,
+                    Err(e) => {
+                        error!("OTORITAS: Gagal membaca parameter chain: {}", e);
+                        sleep(Duration::from_secs(state::DEFAULT_BLOCK_INTERVAL_SECS)).await;
+                        continue;
+                    }
+                };
+                sleep(Duration::from_secs(params.block_interval_secs)).await;
+                if !params.is_authority(&key_clone.public_key_bytes()) {
+                    warn!("OTORITAS: Node ini tidak termasuk dalam set otoritas, blok tidak dibuat.");
+                    continue;
+                }
+                let transactions = mempool_clone.get_transactions(params.max_block_transactions as usize);
                 if !transactions.is_empty() {
                     let new_block = {
                         let mut chain = chain_clone.lock().unwrap();
-                        let block = chain.create_block(&key_clone, transactions);
+                        // Salinan kandidat dikembalikan ke mempool jika blok gagal disusun atau ditolak.
+                        let candidates = transactions.clone();
+                        let (block, excluded) = match chain.build_block(&key_clone, transactions) {
+                            Ok(built) => built,
+                            Err(e) => {
+                                error!("OTORITAS: Gagal menyusun blok: {}", e);
+                                mempool_clone.return_transactions(candidates, &chain.state);
+                                continue;
+                            }
+                        };
+                        for skipped in &excluded {
+                            warn!(
+                                "OTORITAS: Transaksi 0x{} tidak dimasukkan ke blok #{}: {}",
+                                hex::encode(skipped.transaction.hash()),
+                                block.index,
+                                skipped.reason
+                            );
+                        }
+                        let mut retry = Vec::new();
+                        for skipped in excluded {
+                            if skipped.retry {
+                                retry.push(skipped.transaction);
+                            } else {
+                                mempool_clone.record_dropped(skipped.transaction.hash(), skipped.reason);
+                            }
+                        }
+                        if block.transactions.is_empty() {
+                            mempool_clone.return_transactions(retry, &chain.state);
+                            continue;
+                        }
                         info!(
                             "OTORITAS: Membuat blok baru #{} dengan {} transaksi.",
                             block.index,
                             block.transactions.len()
                         );
                         if chain.add_block(block.clone()) {
+                            mempool_clone.return_transactions(retry, &chain.state);
                             block
                         } else {
                             error!("OTORITAS: Gagal menambahkan blok yang baru dibuat ke chain lokal.");
+                            // Kandidat yang sudah dibuang lewat `record_dropped` tidak lagi
+                            // in-flight dan dilewati.
+                            mempool_clone.return_transactions(candidates, &chain.state);
                             continue;
                         }
                     };
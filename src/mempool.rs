@@ -1,38 +1,445 @@
 // src/mempool.rs
 
-use crate::blockchain::TransactionData;
-use crate::blockchain::Transaction;
-use crate::state::StateMachine;
-use std::collections::HashSet;
+use crate::blockchain::{Block, BlockObserver, Transaction, TransactionData};
+use crate::journal::Journal;
+use crate::sigcache::SignatureCache;
+use crate::crypto::TaggedPublicKey;
+use crate::state::{self, Account, Address, StateMachine, NAME_REGISTRATION_FEE};
+use serde::Serialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+use std::io;
+use std::path::Path;
 use std::sync::{ Arc, Mutex };
-use log::{ debug, warn };
+use log::{ debug, info, warn };
+
+// Kenaikan fee minimum (persen) agar transaksi dengan nonce yang sama dapat menggantikan
+// transaksi yang sedang menunggu.
+pub const DEFAULT_REPLACEMENT_FEE_BUMP_PERCENT: u64 = 10;
+
+pub const DEFAULT_MAX_TRANSACTIONS: usize = 10_000;
+pub const DEFAULT_MAX_BYTES: usize = 32 * 1024 * 1024;
+pub const DEFAULT_MAX_PER_SENDER: usize = 64;
+
+// Jumlah transaksi terbuang terakhir yang alasannya masih bisa ditanyakan lewat `status`.
+const MAX_DROPPED_RECORDS: usize = 10_000;
+
+const DROP_REPLACED: &str = "Digantikan transaksi dengan nonce sama dan fee lebih tinggi";
+const DROP_EVICTED: &str = "Digeser dari mempool yang penuh oleh transaksi ber-fee lebih tinggi";
+const DROP_NONCE_USED: &str = "Nonce sudah terpakai di chain";
+
+// Jurnal ditulis ulang jika jumlah record melebihi dua kali isi mempool ditambah nilai ini.
+const JOURNAL_COMPACTION_SLACK: usize = 1_000;
+
+// Di atas tingkat keterisian ini (persen) fee minimum mempool mulai naik.
+const FEE_FLOOR_START_PERCENT: usize = 50;
+// Persentil fee yang menunggu yang dipakai sebagai fee minimum saat mempool penuh.
+const FEE_FLOOR_MAX_PERCENTILE: usize = 90;
+
+// Penolakan karena kapasitas. Pesan lain dari `add_transaction` berarti transaksinya
+// sendiri tidak valid; pesan-pesan ini berarti transaksi mungkin diterima nanti atau
+// dengan fee lebih tinggi.
+pub const ERR_POOL_FULL: &str = "Mempool penuh, fee terlalu rendah untuk menggeser transaksi lain";
+pub const ERR_SENDER_LIMIT: &str = "Terlalu banyak transaksi menunggu dari pengirim ini";
+pub const ERR_FEE_FLOOR: &str = "Biaya transaksi di bawah batas minimum mempool saat ini";
+
+pub fn is_capacity_error(error: &str) -> bool {
+    [ERR_POOL_FULL, ERR_SENDER_LIMIT, ERR_FEE_FLOOR].contains(&error)
+}
+
+pub const ERR_DATABASE: &str = "Gagal akses database";
+pub const ERR_UNKNOWN_SENDER: &str = "Akun pengirim tidak ditemukan";
+pub const ERR_INVALID_SIGNATURE: &str = "Tanda tangan tidak valid";
+pub const ERR_SIGNER_MISMATCH: &str = "Tanda tangan tidak cocok dengan kunci penanda tangan yang disertakan";
+pub const ERR_STALE_NONCE: &str = "Nonce sudah usang (replay attack?)";
+pub const ERR_FEE_BELOW_MINIMUM: &str = "Biaya transaksi di bawah minimum";
+pub const ERR_INSUFFICIENT_BALANCE: &str = "Saldo tidak cukup";
+pub const ERR_AMOUNT_OVERFLOW: &str = "Jumlah ditambah biaya melebihi batas";
+pub const ERR_DUPLICATE: &str = "Transaksi sudah ada di mempool";
+
+// True jika transaksinya sendiri rusak (tanda tangan tidak valid, jumlah meluap),
+// sehingga peer yang menyiarkannya layak diberi penalti. Payload yang gagal di-decode
+// sudah ditolak di lapisan P2P. Kegagalan yang bergantung pada state lokal yang bisa
+// tertinggal dari peer (akun tidak dikenal, nonce, saldo, fee minimum, kapasitas pool)
+// hanya diabaikan.
+pub fn is_peer_fault(error: &str) -> bool {
+    [ERR_INVALID_SIGNATURE, ERR_SIGNER_MISMATCH, ERR_AMOUNT_OVERFLOW].contains(&error)
+}
+
+// Kebijakan lokal node, tidak termasuk aturan konsensus.
+#[derive(Debug, Clone)]
+pub struct MempoolConfig {
+    pub replacement_fee_bump_percent: u64,
+    pub max_transactions: usize,
+    // Total ukuran serialisasi bincode seluruh transaksi yang menunggu.
+    pub max_bytes: usize,
+    pub max_per_sender: usize,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            replacement_fee_bump_percent: DEFAULT_REPLACEMENT_FEE_BUMP_PERCENT,
+            max_transactions: DEFAULT_MAX_TRANSACTIONS,
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_per_sender: DEFAULT_MAX_PER_SENDER,
+        }
+    }
+}
+
+// Sebaran fee transaksi yang menunggu, untuk membantu dompet memilih fee.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeStats {
+    pub pending: usize,
+    pub min: u64,
+    pub p25: u64,
+    pub median: u64,
+    pub p75: u64,
+    pub max: u64,
+    // Fee minimum yang saat ini diminta mempool karena tingkat keterisiannya.
+    pub floor: u64,
+}
+
+// Keadaan transaksi yang diketahui mempool.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxStatus {
+    // `ready` false berarti transaksi menunggu celah nonce sebelumnya terisi.
+    Pending { ready: bool },
+    Dropped { reason: String },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MempoolUsage {
+    pub count: usize,
+    pub bytes: usize,
+    pub ready: usize,
+    pub future: usize,
+    pub max_transactions: usize,
+    pub max_bytes: usize,
+}
+
+// Transaksi terdepan (nonce terkecil) milik satu pengirim, diurutkan berdasarkan fee.
+// Ukuran blok dibatasi jumlah transaksi, bukan byte, sehingga fee absolut
+// adalah ukuran keuntungan yang tepat per slot blok.
+struct Candidate {
+    fee: u64,
+    tx_hash: [u8; 32],
+    sender: Address,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Fee sama: hash terkecil didahulukan agar urutan deterministik.
+        self.fee.cmp(&other.fee).then_with(|| other.tx_hash.cmp(&self.tx_hash))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+// Antrean transaksi satu pengirim, diurutkan berdasarkan nonce. Transaksi "ready"
+// adalah deret bersambung mulai dari `next_nonce`; sisanya "future" dan menunggu
+// celah nonce terisi.
+#[derive(Default)]
+struct SenderQueue {
+    // Nonce berikutnya yang dapat dieksekusi: nonce akun, atau satu setelah
+    // transaksi terakhir yang sudah diserahkan ke produsen blok.
+    next_nonce: u64,
+    transactions: BTreeMap<u64, Transaction>,
+}
+
+impl SenderQueue {
+    fn ready(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions
+            .range(self.next_nonce..)
+            .zip(self.next_nonce..)
+            .take_while(|((nonce, _), expected)| **nonce == *expected)
+            .map(|((_, tx), _)| tx)
+    }
+
+    fn ready_len(&self) -> usize {
+        self.ready().count()
+    }
+}
+
+// Saldo minimum yang dibutuhkan pengirim, berdasarkan jenis transaksi. `None` jika
+// jumlahnya melebihi u64, yang juga akan ditolak saat transaksi diterapkan.
+fn required_balance(tx: &Transaction) -> Option<u64> {
+    match &tx.data {
+        TransactionData::Transfer { amount, .. } => amount.checked_add(tx.fee),
+        TransactionData::TransferToName { amount, .. } => amount.checked_add(tx.fee),
+        TransactionData::Stake { amount } => amount.checked_add(tx.fee),
+        TransactionData::HtlcLock { amount, .. } => amount.checked_add(tx.fee),
+        TransactionData::CallContract { amount, .. } => amount.checked_add(tx.fee),
+        TransactionData::HtlcClaim { .. }
+        | TransactionData::HtlcRefund { .. }
+        | TransactionData::DeployContract { .. }
+        | TransactionData::CreateAsset { .. }
+        | TransactionData::MintAsset { .. }
+        | TransactionData::BurnAsset { .. }
+        | TransactionData::TransferAsset { .. }
+        | TransactionData::MintNft { .. }
+        | TransactionData::TransferNft { .. }
+        | TransactionData::BurnNft { .. }
+        | TransactionData::TransferName { .. }
+        | TransactionData::Propose { .. }
+        | TransactionData::Vote { .. }
+        | TransactionData::RotateKey { .. } => Some(tx.fee),
+        TransactionData::RegisterName { .. } | TransactionData::RenewName { .. } => {
+            NAME_REGISTRATION_FEE.checked_add(tx.fee)
+        }
+    }
+}
+
+fn tx_size(tx: &Transaction) -> usize {
+    bincode::serialized_size(tx).map_or(usize::MAX, |size| size as usize)
+}
+
+// Catatan terbatas (FIFO) transaksi yang dibuang beserta alasannya.
+#[derive(Default)]
+struct DroppedLog {
+    reasons: HashMap<[u8; 32], String>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl DroppedLog {
+    fn record(&mut self, tx_hash: [u8; 32], reason: String) {
+        if self.reasons.insert(tx_hash, reason).is_none() {
+            self.order.push_back(tx_hash);
+        }
+        while self.order.len() > MAX_DROPPED_RECORDS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.reasons.remove(&oldest);
+            }
+        }
+    }
+}
+
+// Isi mempool beserta total jumlah dan ukurannya, dijaga bersama di bawah satu lock.
+#[derive(Default)]
+struct Pool {
+    senders: HashMap<Address, SenderQueue>,
+    count: usize,
+    bytes: usize,
+    // Indeks hash transaksi ke posisinya di `senders`.
+    by_hash: HashMap<[u8; 32], (Address, u64)>,
+    // Transaksi yang sudah diserahkan `get_transactions` ke produsen blok. Tidak dihitung
+    // di `count`/`bytes`; dikembalikan ke antreannya jika blok berikutnya tidak memuatnya.
+    in_flight: HashMap<[u8; 32], Transaction>,
+    dropped: DroppedLog,
+}
+
+impl Pool {
+    fn add(&mut self, tx: Transaction) {
+        self.count += 1;
+        self.bytes += tx_size(&tx);
+        self.by_hash.insert(tx.hash(), (tx.sender, tx.nonce));
+        self.senders.entry(tx.sender).or_default().transactions.insert(tx.nonce, tx);
+    }
+
+    fn remove(&mut self, sender: &Address, nonce: u64) -> Option<Transaction> {
+        let tx = self.senders.get_mut(sender)?.transactions.remove(&nonce)?;
+        self.count -= 1;
+        self.bytes -= tx_size(&tx);
+        self.by_hash.remove(&tx.hash());
+        Some(tx)
+    }
+
+    // Seperti `remove`, tetapi alasannya dicatat untuk `Mempool::status`.
+    fn discard(&mut self, sender: &Address, nonce: u64, reason: &str) -> Option<Transaction> {
+        let tx = self.remove(sender, nonce)?;
+        self.dropped.record(tx.hash(), reason.to_string());
+        Some(tx)
+    }
+
+    // Menaikkan `next_nonce` pengirim dan membuang transaksi yang nonce-nya sudah terlewati.
+    fn advance_to(&mut self, sender: &Address, nonce: u64) -> Vec<Transaction> {
+        let queue = self.senders.entry(*sender).or_default();
+        if nonce <= queue.next_nonce {
+            return Vec::new();
+        }
+        queue.next_nonce = nonce;
+        let stale: Vec<u64> = queue.transactions.range(..nonce).map(|(nonce, _)| *nonce).collect();
+        stale
+            .into_iter()
+            .filter_map(|stale| self.discard(sender, stale, DROP_NONCE_USED))
+            .collect()
+    }
+
+    // Kunci penandatangan pengirim untuk transaksi dengan `nonce` jika transaksinya yang
+    // lebih awal di mempool (termasuk yang in-flight) dieksekusi lebih dulu: hasil
+    // `RotateKey` terakhir sebelum nonce itu. `None` jika tidak ada rotasi tertunda.
+    fn pending_signing_key(&self, sender: &Address, nonce: u64) -> Option<Option<TaggedPublicKey>> {
+        let queued = self
+            .senders
+            .get(sender)
+            .into_iter()
+            .flat_map(|queue| queue.transactions.range(..nonce).map(|(_, tx)| tx));
+        let in_flight = self
+            .in_flight
+            .values()
+            .filter(|tx| tx.sender == *sender && tx.nonce < nonce);
+        queued
+            .chain(in_flight)
+            .filter_map(|tx| match &tx.data {
+                TransactionData::RotateKey { new_key } => Some((tx.nonce, new_key)),
+                _ => None,
+            })
+            .max_by_key(|(nonce, _)| *nonce)
+            .map(|(_, new_key)| state::rotated_signing_key(sender, new_key))
+    }
+
+    fn fees(&self) -> Vec<u64> {
+        let mut fees: Vec<u64> = self.senders
+            .values()
+            .flat_map(|queue| queue.transactions.values().map(|tx| tx.fee))
+            .collect();
+        fees.sort_unstable();
+        fees
+    }
+}
 
 #[derive(Clone)]
 pub struct Mempool {
-    transactions: Arc<Mutex<HashSet<Transaction>>>,
+    pool: Arc<Mutex<Pool>>,
+    signature_cache: SignatureCache,
+    config: MempoolConfig,
+    // Hanya aktif setelah `open_journal`. Lock `journal` diambil sebelum lock `pool`.
+    journal: Arc<Mutex<Option<Journal>>>,
+    // Transaksi yang sudah masuk mempool tetapi belum ditulis `flush_journal`; `None`
+    // jika jurnal tidak aktif. Lock ini tidak pernah menunggu disk.
+    journal_queue: Arc<Mutex<Option<Vec<Transaction>>>>,
 }
 
 impl Mempool {
     pub fn new() -> Self {
+        Self::with_cache(SignatureCache::default())
+    }
+
+    // Mempool yang berbagi cache verifikasi dengan `Blockchain::signature_cache`.
+    pub fn with_cache(signature_cache: SignatureCache) -> Self {
+        Self::with_config(signature_cache, MempoolConfig::default())
+    }
+
+    pub fn with_config(signature_cache: SignatureCache, config: MempoolConfig) -> Self {
         Self {
-            transactions: Arc::new(Mutex::new(HashSet::new())),
+            pool: Arc::new(Mutex::new(Pool::default())),
+            signature_cache,
+            config,
+            journal: Arc::new(Mutex::new(None)),
+            journal_queue: Arc::new(Mutex::new(None)),
         }
     }
 
+    // Fee terendah yang dibutuhkan untuk menggantikan transaksi dengan fee `current_fee`.
+    // Selalu lebih tinggi minimal 1 agar penggantian tanpa kenaikan fee tidak bisa dipakai
+    // untuk membanjiri jaringan.
+    pub fn replacement_fee(&self, current_fee: u64) -> u64 {
+        let bump = current_fee
+            .saturating_mul(self.config.replacement_fee_bump_percent)
+            .div_ceil(100)
+            .max(1);
+        current_fee.saturating_add(bump)
+    }
+
+    // Tingkat keterisian mempool dalam persen, diukur dari batas yang paling dekat.
+    fn fill_percent(&self, pool: &Pool) -> usize {
+        let by_count = pool.count * 100 / self.config.max_transactions.max(1);
+        let by_bytes = pool.bytes * 100 / self.config.max_bytes.max(1);
+        by_count.max(by_bytes)
+    }
+
+    // Fee minimum dinamis: nol sampai mempool setengah penuh, lalu naik mengikuti
+    // persentil fee yang menunggu hingga persentil ke-90 saat penuh. Pengirim yang
+    // ingin masuk ke mempool yang ramai harus bersaing dengan isi mempool itu sendiri.
+    fn fee_floor(&self, pool: &Pool, fees: &[u64]) -> u64 {
+        let fill = self.fill_percent(pool);
+        if fill < FEE_FLOOR_START_PERCENT || fees.is_empty() {
+            return 0;
+        }
+        let percentile = ((fill - FEE_FLOOR_START_PERCENT) * FEE_FLOOR_MAX_PERCENTILE
+            / (100 - FEE_FLOOR_START_PERCENT))
+            .min(FEE_FLOOR_MAX_PERCENTILE);
+        fees[(fees.len() - 1) * percentile / 100]
+    }
+
+    // Transaksi dari RPC lokal. Selain divalidasi, transaksi yang diterima diantrekan untuk
+    // jurnal; pemanggil memanggil `flush_journal` setelah melepas lock-nya sendiri.
     pub fn add_transaction(
         &self,
         tx: Transaction,
         state: &StateMachine
     ) -> Result<(), &'static str> {
-        if !tx.verify() {
-            warn!("MEMPOOL: Ditolak, tanda tangan tidak valid.");
-            return Err("Tanda tangan tidak valid");
+        let account_nonce = self.validate(&tx, state)?;
+        let mut pool = self.pool.lock().unwrap();
+        let mut journal_queue = self.journal_queue.lock().unwrap();
+        let journaled = journal_queue.is_some().then(|| tx.clone());
+        self.insert(&mut pool, tx, account_nonce)?;
+        if let (Some(queue), Some(tx)) = (journal_queue.as_mut(), journaled) {
+            queue.push(tx);
         }
+        Ok(())
+    }
 
-        let sender_account = state
+    // Transaksi dari gossip melewati validasi yang sama dengan `add_transaction`.
+    // Lapisan P2P memakai `is_peer_fault` pada error untuk menilai peer pengirimnya.
+    pub fn add_from_p2p(&self, tx: Transaction, state: &StateMachine) -> Result<(), &'static str> {
+        let account_nonce = self.validate(&tx, state)?;
+        let mut pool = self.pool.lock().unwrap();
+        self.insert(&mut pool, tx, account_nonce)?;
+        debug!("MEMPOOL: Transaksi dari P2P ditambahkan.");
+        Ok(())
+    }
+
+    // Validasi terhadap state untuk semua jalur masuk. Mengembalikan nonce akun pengirim.
+    // Tanda tangan diperiksa terhadap kunci hasil `RotateKey` yang masih menunggu di
+    // mempool, agar pengirim bisa langsung memakai kunci barunya.
+    fn validate(&self, tx: &Transaction, state: &StateMachine) -> Result<u64, &'static str> {
+        let mut sender_account = state
             .get_account(&tx.sender)
-            .map_err(|_| "Gagal akses database")?
-            .ok_or("Akun pengirim tidak ditemukan")?;
+            .map_err(|_| ERR_DATABASE)?
+            .ok_or(ERR_UNKNOWN_SENDER)?;
+        if let Some(pending_key) = self.pool.lock().unwrap().pending_signing_key(&tx.sender, tx.nonce) {
+            sender_account.signing_key = pending_key;
+        }
+        let min_fee = state
+            .get_params()
+            .map_err(|_| ERR_DATABASE)?
+            .min_fee;
+        self.check_against_account(tx, &sender_account, min_fee)?;
+        Ok(sender_account.nonce)
+    }
+
+    // Pemeriksaan yang hanya bergantung pada akun pengirim; dipakai juga saat memvalidasi
+    // ulang isi mempool setelah blok baru.
+    fn check_against_account(
+        &self,
+        tx: &Transaction,
+        sender_account: &Account,
+        min_fee: u64
+    ) -> Result<(), &'static str> {
+        if !tx.verify_cached(sender_account.signing_key.as_ref(), &self.signature_cache) {
+            warn!("MEMPOOL: Ditolak, tanda tangan tidak valid.");
+            // Kunci terdaftar bisa berubah lewat rotasi, tetapi tanda tangan yang gagal
+            // terhadap kunci yang disertakan transaksi itu sendiri tidak akan pernah valid.
+            let signer_mismatch = tx
+                .signer
+                .as_ref()
+                .is_some_and(|key| !key.verify(&tx.message_hash(), tx.signature_scheme, &tx.signature));
+            return Err(if signer_mismatch { ERR_SIGNER_MISMATCH } else { ERR_INVALID_SIGNATURE });
+        }
 
         if tx.nonce < sender_account.nonce {
             warn!(
@@ -40,44 +447,293 @@ impl Mempool {
                 sender_account.nonce,
                 tx.nonce
             );
-            return Err("Nonce sudah usang (replay attack?)");
+            return Err(ERR_STALE_NONCE);
         }
 
-        // Cek saldo berdasarkan jenis transaksi
-        let required_balance = match &tx.data {
-            TransactionData::Transfer { amount, .. } => amount + tx.fee,
-            TransactionData::Stake { amount } => amount + tx.fee,
-        };
+        if tx.fee < min_fee {
+            warn!("MEMPOOL: Ditolak, biaya {} di bawah minimum {}.", tx.fee, min_fee);
+            return Err(ERR_FEE_BELOW_MINIMUM);
+        }
 
+        let Some(required_balance) = required_balance(tx) else {
+            warn!("MEMPOOL: Ditolak, jumlah ditambah biaya melebihi batas.");
+            return Err(ERR_AMOUNT_OVERFLOW);
+        };
         if sender_account.balance < required_balance {
             warn!(
                 "MEMPOOL: Ditolak, saldo tidak cukup (memiliki {}, butuh {}).",
                 sender_account.balance,
                 required_balance
             );
-            return Err("Saldo tidak cukup");
+            return Err(ERR_INSUFFICIENT_BALANCE);
+        }
+        Ok(())
+    }
+
+    // Menulis transaksi yang diantrekan `add_transaction` ke jurnal dalam satu batch dengan
+    // satu fsync, tanpa memegang lock `pool` selama menulis. Kegagalan menulis tidak
+    // membatalkan transaksi yang sudah masuk mempool; akibatnya hanya transaksi itu tidak
+    // dimuat ulang setelah restart.
+    pub fn flush_journal(&self) {
+        let mut journal = self.journal.lock().unwrap();
+        let Some(journal) = journal.as_mut() else {
+            return;
+        };
+        let batch = match self.journal_queue.lock().unwrap().as_mut() {
+            Some(queue) => std::mem::take(queue),
+            None => return,
+        };
+        if batch.is_empty() {
+            return;
+        }
+        if let Err(e) = journal.append(&batch) {
+            warn!("MEMPOOL: Gagal menulis jurnal {}: {}", journal.path().display(), e);
+            return;
         }
+        // Transaksi yang masuk setelah salinan ini diambil tetap ada di antrean dan ditulis
+        // ke jurnal baru pada flush berikutnya.
+        let snapshot: Vec<Transaction> = {
+            let pool = self.pool.lock().unwrap();
+            if journal.records() <= pool.count * 2 + JOURNAL_COMPACTION_SLACK {
+                return;
+            }
+            Self::pooled(&pool).cloned().collect()
+        };
+        match Journal::rewrite(journal.path(), &snapshot) {
+            Ok(compacted) => *journal = compacted,
+            Err(e) => warn!("MEMPOOL: Gagal memadatkan jurnal {}: {}", journal.path().display(), e),
+        }
+    }
+
+    // Seluruh transaksi di mempool, berurutan menurut nonce per pengirim, ditambah yang
+    // in-flight agar tetap ada di jurnal sampai masuk blok.
+    fn pooled(pool: &Pool) -> impl Iterator<Item = &Transaction> {
+        pool.senders
+            .values()
+            .flat_map(|queue| queue.transactions.values())
+            .chain(pool.in_flight.values())
+    }
 
-        let mut pool = self.transactions.lock().unwrap();
-        if pool.insert(tx) {
-            debug!("MEMPOOL: Transaksi baru ditambahkan. Total di mempool: {}", pool.len());
-            Ok(())
-        } else {
-            warn!("MEMPOOL: Ditolak, transaksi sudah ada di mempool.");
-            Err("Transaksi sudah ada di mempool")
+    // Memuat ulang transaksi dari jurnal di `path` dan memvalidasinya ulang terhadap state
+    // saat ini seperti transaksi baru; yang sudah tidak valid dibuang. Setelah itu jurnal
+    // ditulis ulang berisi isi mempool dan transaksi yang diterima berikutnya dicatat di sana.
+    // Dipanggil sekali saat node mulai. Mengembalikan jumlah transaksi yang dipulihkan.
+    pub fn open_journal(&self, path: &Path, state: &StateMachine) -> io::Result<usize> {
+        let journaled = Journal::read(path)?;
+        let total = journaled.len();
+        let mut restored = 0;
+        for tx in journaled {
+            match self.add_transaction(tx, state) {
+                Ok(()) => restored += 1,
+                Err(e) => debug!("MEMPOOL: Transaksi dari jurnal dibuang: {}", e),
+            }
         }
+        let mut journal = self.journal.lock().unwrap();
+        *self.journal_queue.lock().unwrap() = Some(Vec::new());
+        let snapshot: Vec<Transaction> = Self::pooled(&self.pool.lock().unwrap()).cloned().collect();
+        *journal = Some(Journal::rewrite(path, &snapshot)?);
+        info!(
+            "MEMPOOL: {} dari {} transaksi dipulihkan dari jurnal {}.",
+            restored,
+            total,
+            path.display()
+        );
+        Ok(restored)
     }
 
+    fn insert(
+        &self,
+        pool: &mut Pool,
+        tx: Transaction,
+        account_nonce: u64
+    ) -> Result<(), &'static str> {
+        let sender = tx.sender;
+        let result = self.try_insert(pool, tx, account_nonce);
+        // Antrean kosong yang tidak lagi menyimpan nonce tertunda tidak perlu dipertahankan,
+        // agar transaksi yang ditolak dari banyak alamat baru tidak menumpuk entri.
+        if pool.senders.get(&sender).is_some_and(|queue| {
+            queue.transactions.is_empty() && queue.next_nonce <= account_nonce
+        }) {
+            pool.senders.remove(&sender);
+        }
+        result
+    }
 
+    fn try_insert(
+        &self,
+        pool: &mut Pool,
+        tx: Transaction,
+        account_nonce: u64
+    ) -> Result<(), &'static str> {
+        for stale in pool.advance_to(&tx.sender, account_nonce) {
+            self.signature_cache.remove(&stale.hash());
+        }
+        let queue = pool.senders.entry(tx.sender).or_default();
+        if tx.nonce < queue.next_nonce {
+            // Nonce ini sudah diserahkan ke produsen blok dan menunggu dikonfirmasi.
+            warn!("MEMPOOL: Ditolak, nonce {} sudah diambil untuk blok.", tx.nonce);
+            return Err(ERR_STALE_NONCE);
+        }
+        if let Some(existing) = queue.transactions.get(&tx.nonce) {
+            if existing.hash() == tx.hash() {
+                warn!("MEMPOOL: Ditolak, transaksi sudah ada di mempool.");
+                return Err(ERR_DUPLICATE);
+            }
+            let required_fee = self.replacement_fee(existing.fee);
+            if tx.fee < required_fee {
+                warn!(
+                    "MEMPOOL: Ditolak, fee pengganti {} untuk nonce {} kurang dari {}.",
+                    tx.fee,
+                    tx.nonce,
+                    required_fee
+                );
+                return Err("Fee pengganti terlalu rendah untuk menggantikan transaksi dengan nonce yang sama");
+            }
+            debug!(
+                "MEMPOOL: Transaksi nonce {} diganti (fee {} -> {}).",
+                tx.nonce,
+                existing.fee,
+                tx.fee
+            );
+            // Penggantian tidak menambah jumlah transaksi, sehingga hanya kenaikan
+            // fee di atas yang berlaku dan bukan batas kapasitas.
+            self.discard(pool, &tx.sender, tx.nonce, DROP_REPLACED);
+            pool.add(tx);
+            return Ok(());
+        }
+
+        if queue.transactions.len() >= self.config.max_per_sender {
+            warn!("MEMPOOL: Ditolak, pengirim sudah memiliki {} transaksi menunggu.", queue.transactions.len());
+            return Err(ERR_SENDER_LIMIT);
+        }
+        let floor = self.fee_floor(pool, &pool.fees());
+        if tx.fee < floor {
+            warn!("MEMPOOL: Ditolak, biaya {} di bawah batas mempool {}.", tx.fee, floor);
+            return Err(ERR_FEE_FLOOR);
+        }
+        for (sender, nonce) in self.plan_eviction(pool, &tx, tx_size(&tx))? {
+            if let Some(evicted) = self.discard(pool, &sender, nonce, DROP_EVICTED) {
+                debug!("MEMPOOL: Transaksi fee {} digeser untuk memberi tempat.", evicted.fee);
+            }
+        }
+
+        let sender = tx.sender;
+        let ready_before = pool.senders[&sender].ready_len();
+        pool.add(tx);
+        let promoted = pool.senders[&sender].ready_len().saturating_sub(ready_before + 1);
+        if promoted > 0 {
+            debug!("MEMPOOL: Celah nonce terisi, {} transaksi future menjadi ready.", promoted);
+        }
+        debug!("MEMPOOL: Transaksi baru ditambahkan. Total di mempool: {}", pool.count);
+        Ok(())
+    }
+
+    // Memilih transaksi ber-fee terendah yang harus dikeluarkan agar `tx` muat. Hanya
+    // transaksi dengan nonce tertinggi milik pengirim lain yang dapat digeser, sehingga
+    // antrean yang tersisa tetap bersambung. Gagal jika yang tergeser tidak lebih murah.
+    fn plan_eviction(
+        &self,
+        pool: &Pool,
+        tx: &Transaction,
+        size: usize
+    ) -> Result<Vec<(Address, u64)>, &'static str> {
+        let mut count = pool.count + 1;
+        let mut bytes = pool.bytes.saturating_add(size);
+        let fits = |count: usize, bytes: usize| {
+            count <= self.config.max_transactions && bytes <= self.config.max_bytes
+        };
+        if fits(count, bytes) {
+            return Ok(Vec::new());
+        }
+
+        let mut tails: Vec<_> = pool.senders
+            .iter()
+            .filter(|(sender, _)| **sender != tx.sender)
+            .map(|(sender, queue)| (sender, queue.transactions.iter().rev()))
+            .collect();
+        let mut heap = BinaryHeap::new();
+        for (index, (_, tail)) in tails.iter_mut().enumerate() {
+            if let Some((nonce, pending)) = tail.next() {
+                heap.push(Reverse((pending.fee, index, *nonce, tx_size(pending))));
+            }
+        }
+
+        let mut victims = Vec::new();
+        while !fits(count, bytes) {
+            let Some(Reverse((fee, index, nonce, victim_size))) = heap.pop() else {
+                warn!("MEMPOOL: Ditolak, transaksi lebih besar dari kapasitas mempool.");
+                return Err(ERR_POOL_FULL);
+            };
+            if fee >= tx.fee {
+                warn!("MEMPOOL: Ditolak, mempool penuh dan fee {} tidak melebihi fee terendah {}.", tx.fee, fee);
+                return Err(ERR_POOL_FULL);
+            }
+            victims.push((*tails[index].0, nonce));
+            count -= 1;
+            bytes -= victim_size;
+            if let Some((nonce, pending)) = tails[index].1.next() {
+                heap.push(Reverse((pending.fee, index, *nonce, tx_size(pending))));
+            }
+        }
+        Ok(victims)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pool.lock().unwrap().count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Total ukuran serialisasi transaksi yang menunggu, dalam byte.
+    pub fn size_bytes(&self) -> usize {
+        self.pool.lock().unwrap().bytes
+    }
+
+    // Jumlah transaksi yang nonce-nya bersambung dengan nonce akun dan siap masuk blok.
+    pub fn ready_len(&self) -> usize {
+        self.pool.lock().unwrap().senders.values().map(SenderQueue::ready_len).sum()
+    }
+
+    // Jumlah transaksi yang menunggu celah nonce terisi.
+    pub fn future_len(&self) -> usize {
+        let pool = self.pool.lock().unwrap();
+        pool.count - pool.senders.values().map(SenderQueue::ready_len).sum::<usize>()
+    }
+
+    // Mengambil hingga `count` transaksi ready dengan fee tertinggi. Transaksi satu
+    // pengirim tetap diambil berurutan menurut nonce: transaksi berikutnya baru menjadi
+    // kandidat setelah transaksi sebelumnya terpilih. Transaksi future tidak pernah diambil.
     pub fn get_transactions(&self, count: usize) -> Vec<Transaction> {
-        let mut pool = self.transactions.lock().unwrap();
+        let mut pool = self.pool.lock().unwrap();
 
-        let transactions_to_take: Vec<Transaction> = pool.iter().take(count).cloned().collect();
+        let mut heap = BinaryHeap::new();
+        for (sender, queue) in pool.senders.iter() {
+            if let Some(head) = queue.transactions.get(&queue.next_nonce) {
+                heap.push(Candidate { fee: head.fee, tx_hash: head.hash(), sender: *sender });
+            }
+        }
 
-        for tx in &transactions_to_take {
-            pool.remove(tx);
+        let mut transactions_to_take: Vec<Transaction> = Vec::new();
+        while transactions_to_take.len() < count {
+            let Some(candidate) = heap.pop() else { break };
+            let next_nonce = pool.senders[&candidate.sender].next_nonce;
+            let tx = pool.remove(&candidate.sender, next_nonce).unwrap();
+            pool.in_flight.insert(tx.hash(), tx.clone());
+            let queue = pool.senders.get_mut(&candidate.sender).unwrap();
+            queue.next_nonce += 1;
+            transactions_to_take.push(tx);
+            if let Some(next) = queue.transactions.get(&queue.next_nonce) {
+                heap.push(Candidate { fee: next.fee, tx_hash: next.hash(), sender: candidate.sender });
+            }
         }
 
+        // Antrean yang kosong tetap disimpan agar `next_nonce` diingat sampai blok
+        // yang memuat transaksinya dikonfirmasi dan nonce akun ikut naik. Transaksi yang
+        // diambil tetap dicatat in-flight sampai blok berikutnya, `return_transactions`
+        // atau `record_dropped`, sehingga tidak hilang jika pemanggil gagal menyusun blok.
+
         if !transactions_to_take.is_empty() {
             debug!("MEMPOOL: Mengambil {} transaksi untuk blok baru.", transactions_to_take.len());
         }
@@ -85,113 +741,695 @@ impl Mempool {
         transactions_to_take
     }
 
-    pub fn add_from_p2p(&self, tx: Transaction) {
-        if tx.verify() {
-            let mut pool = self.transactions.lock().unwrap();
-            if pool.insert(tx) {
-                debug!("MEMPOOL: Transaksi dari P2P ditambahkan. Total di mempool: {}", pool.len());
+    // Mengembalikan transaksi yang diambil `get_transactions` tetapi tidak masuk blok.
+    // Hanya dipanggil saat tidak ada blok yang sedang disusun, sehingga nonce akun di
+    // `state` adalah acuan yang benar untuk `next_nonce` pengirimnya. Transaksi yang sudah
+    // tidak in-flight (sudah dikembalikan oleh blok yang masuk sementara itu) dilewati.
+    pub fn return_transactions(&self, transactions: Vec<Transaction>, state: &StateMachine) {
+        let mut pool = self.pool.lock().unwrap();
+        for tx in transactions {
+            if pool.in_flight.remove(&tx.hash()).is_none() {
+                continue;
+            }
+            let account_nonce = match state.get_account(&tx.sender) {
+                Ok(account) => account.map_or(0, |account| account.nonce),
+                Err(e) => {
+                    warn!("MEMPOOL: Gagal membaca akun pengirim dari database: {}", e);
+                    continue;
+                }
+            };
+            let queue = pool.senders.entry(tx.sender).or_default();
+            queue.next_nonce = queue.next_nonce.min(account_nonce);
+            let tx_hash = tx.hash();
+            if let Err(e) = self.insert(&mut pool, tx, account_nonce) {
+                debug!("MEMPOOL: Transaksi tidak dapat dikembalikan ke mempool: {}", e);
+                pool.dropped.record(tx_hash, e.to_string());
+            }
+        }
+    }
+
+    pub fn fee_stats(&self) -> FeeStats {
+        let pool = self.pool.lock().unwrap();
+        let fees = pool.fees();
+        if fees.is_empty() {
+            return FeeStats::default();
+        }
+        let percentile = |p: usize| fees[(fees.len() - 1) * p / 100];
+        FeeStats {
+            pending: fees.len(),
+            min: fees[0],
+            p25: percentile(25),
+            median: percentile(50),
+            p75: percentile(75),
+            max: fees[fees.len() - 1],
+            floor: self.fee_floor(&pool, &fees),
+        }
+    }
+
+    pub fn contains(&self, tx_hash: &[u8; 32]) -> bool {
+        self.pool.lock().unwrap().by_hash.contains_key(tx_hash)
+    }
+
+    // Keadaan transaksi di mempool, atau alasan dibuangnya jika masih tercatat.
+    // Transaksi yang sudah masuk chain dicari lewat `Blockchain::find_transaction`.
+    pub fn status(&self, tx_hash: &[u8; 32]) -> Option<TxStatus> {
+        let pool = self.pool.lock().unwrap();
+        if let Some((sender, nonce)) = pool.by_hash.get(tx_hash) {
+            let queue = &pool.senders[sender];
+            let ready = queue.ready().any(|tx| tx.nonce == *nonce);
+            return Some(TxStatus::Pending { ready });
+        }
+        if pool.in_flight.contains_key(tx_hash) {
+            return Some(TxStatus::Pending { ready: true });
+        }
+        pool.dropped
+            .reasons
+            .get(tx_hash)
+            .map(|reason| TxStatus::Dropped { reason: reason.clone() })
+    }
+
+    // Transaksi menunggu milik `address`, berurutan menurut nonce, beserta status ready-nya.
+    pub fn pending_for(&self, address: &Address) -> Vec<(Transaction, bool)> {
+        let pool = self.pool.lock().unwrap();
+        let Some(queue) = pool.senders.get(address) else {
+            return Vec::new();
+        };
+        let ready_end = queue.next_nonce + queue.ready_len() as u64;
+        queue
+            .transactions
+            .values()
+            .map(|tx| (tx.clone(), tx.nonce < ready_end))
+            .collect()
+    }
+
+    // Nonce berikutnya yang dapat dipakai `address`: nonce akun ditambah transaksinya yang
+    // sudah bersambung di mempool (termasuk yang sedang disusun ke blok). Transaksi future
+    // di balik celah nonce tidak dihitung, sehingga nonce ini mengisi celah tersebut.
+    pub fn next_nonce(&self, address: &Address, account_nonce: u64) -> u64 {
+        let pool = self.pool.lock().unwrap();
+        let Some(queue) = pool.senders.get(address) else {
+            return account_nonce;
+        };
+        let mut nonce = account_nonce.max(queue.next_nonce);
+        while queue.transactions.contains_key(&nonce) {
+            nonce += 1;
+        }
+        nonce
+    }
+
+    pub fn usage(&self) -> MempoolUsage {
+        let pool = self.pool.lock().unwrap();
+        let ready = pool.senders.values().map(SenderQueue::ready_len).sum();
+        MempoolUsage {
+            count: pool.count,
+            bytes: pool.bytes,
+            ready,
+            future: pool.count - ready,
+            max_transactions: self.config.max_transactions,
+            max_bytes: self.config.max_bytes,
+        }
+    }
+
+    // Mencatat transaksi yang dibuang di luar mempool, mis. dikecualikan saat menyusun blok.
+    // Transaksi in-flight dengan hash ini dilepas dan tidak dikembalikan ke antrean.
+    pub fn record_dropped(&self, tx_hash: [u8; 32], reason: String) {
+        let mut pool = self.pool.lock().unwrap();
+        pool.in_flight.remove(&tx_hash);
+        pool.dropped.record(tx_hash, reason);
+    }
+
+    fn discard(&self, pool: &mut Pool, sender: &Address, nonce: u64, reason: &str) -> Option<Transaction> {
+        let tx = pool.discard(sender, nonce, reason)?;
+        self.signature_cache.remove(&tx.hash());
+        Some(tx)
+    }
+
+}
+
+impl BlockObserver for Mempool {
+    // Membuang transaksi yang sudah masuk blok dan memvalidasi ulang sisanya terhadap
+    // state baru. Transaksi yang tidak mungkin lagi berhasil (nonce terlewati, saldo
+    // tidak cukup, fee di bawah minimum, atau kunci penandatangan sudah dirotasi) dibuang.
+    fn on_block_added(&self, block: &Block, state: &StateMachine) {
+        let min_fee = match state.get_params() {
+            Ok(params) => params.min_fee,
+            Err(e) => {
+                warn!("MEMPOOL: Gagal membaca parameter chain, validasi ulang dilewati: {}", e);
+                return;
+            }
+        };
+        let mut pool = self.pool.lock().unwrap();
+        // Transaksi in-flight yang tidak dimuat blok ini dikembalikan ke antreannya lalu
+        // divalidasi ulang bersama sisanya. Slot nonce-nya masih kosong karena `try_insert`
+        // menolak nonce di bawah `next_nonce`.
+        for tx in &block.transactions {
+            pool.in_flight.remove(&tx.hash());
+        }
+        let leftover: Vec<Transaction> = pool.in_flight.drain().map(|(_, tx)| tx).collect();
+        for tx in leftover {
+            pool.add(tx);
+        }
+        let senders: Vec<Address> = pool.senders.keys().copied().collect();
+        let mut dropped = 0;
+        for sender in senders {
+            let account = match state.get_account(&sender) {
+                Ok(account) => account,
+                Err(e) => {
+                    warn!("MEMPOOL: Gagal membaca akun pengirim dari database: {}", e);
+                    continue;
+                }
+            };
+            let Some(account) = account else {
+                let nonces: Vec<u64> = pool.senders[&sender].transactions.keys().copied().collect();
+                for nonce in nonces {
+                    self.discard(&mut pool, &sender, nonce, ERR_UNKNOWN_SENDER);
+                    dropped += 1;
+                }
+                pool.senders.remove(&sender);
+                continue;
+            };
+
+            for stale in pool.advance_to(&sender, account.nonce) {
+                self.signature_cache.remove(&stale.hash());
+                dropped += 1;
+            }
+            let queue = pool.senders.get_mut(&sender).unwrap();
+            // Transaksi in-flight sudah kembali ke antrean di atas, jadi nonce akun kini
+            // menjadi acuan satu-satunya.
+            queue.next_nonce = account.nonce;
+
+            // Saldo diperiksa kumulatif menurut urutan nonce: setiap transaksi harus terbayar
+            // dari sisa saldo setelah transaksi sebelumnya yang tetap disimpan. `RotateKey`
+            // yang tetap disimpan menentukan kunci untuk transaksi sesudahnya.
+            let mut remaining = account.clone();
+            let mut invalid: Vec<(u64, &'static str)> = Vec::new();
+            for tx in queue.transactions.values() {
+                match self.check_against_account(tx, &remaining, min_fee) {
+                    Ok(()) => {
+                        remaining.balance -= required_balance(tx).unwrap_or(0);
+                        if let TransactionData::RotateKey { new_key } = &tx.data {
+                            remaining.signing_key = state::rotated_signing_key(&sender, new_key);
+                        }
+                    }
+                    Err(reason) => invalid.push((tx.nonce, reason)),
+                }
+            }
+            for (nonce, reason) in invalid {
+                self.discard(&mut pool, &sender, nonce, reason);
+                dropped += 1;
+            }
+            if pool.senders[&sender].transactions.is_empty() {
+                pool.senders.remove(&sender);
             }
-        } else {
-            warn!("MEMPOOL: Transaksi dari P2P ditolak, tanda tangan tidak valid.");
         }
+        debug!(
+            "MEMPOOL: Setelah blok #{}, {} transaksi dibuang, {} tersisa.",
+            block.index,
+            dropped,
+            pool.count
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blockchain::Transaction;
-    use crate::crypto::{ KeyPair, SIGNATURE_SIZE };
+    use crate::blockchain::{Blockchain, Transaction};
+    use crate::crypto::KeyPair;
     use crate::state::{ Account, StateMachine, Address };
-    use tempfile::tempdir;
+    use tempfile::{tempdir, TempDir};
 
     fn create_test_tx(
         sender_key: &KeyPair,
         recipient: Address,
         amount: u64,
         nonce: u64
+    ) -> Transaction {
+        create_test_tx_with_fee(sender_key, recipient, amount, nonce, 0)
+    }
+
+    fn create_test_tx_with_fee(
+        sender_key: &KeyPair,
+        recipient: Address,
+        amount: u64,
+        nonce: u64,
+        fee: u64
     ) -> Transaction {
         let mut tx = Transaction {
-            sender: sender_key.public_key_bytes(), // <-- PERBAIKAN
-            recipient,
-            amount,
+            sender: sender_key.public_key_bytes(),
+            data: TransactionData::Transfer { recipient, amount },
+            fee,
             nonce,
-            signature: [0; SIGNATURE_SIZE],
+            signer: None,
+            signature_scheme: sender_key.scheme,
+            signature: Vec::new(),
         };
         let hash = tx.message_hash();
-        tx.signature = sender_key.sign(&hash); // <-- PERBAIKAN
+        tx.signature = sender_key.sign(&hash);
         tx
     }
 
-    #[test]
-    fn test_add_valid_transaction() {
+    // State di direktori sementara dengan satu akun Dilithium2 untuk setiap saldo di
+    // `balances`. `TempDir` harus tetap hidup selama test berjalan.
+    fn setup_state<const N: usize>(balances: [u64; N]) -> (TempDir, StateMachine, [KeyPair; N]) {
         let dir = tempdir().unwrap();
         let state = StateMachine::new(dir.path().to_str().unwrap()).unwrap();
+        let users = balances.map(|balance| {
+            let user = KeyPair::new();
+            state.set_account(&user.public_key_bytes(), &Account::new(balance)).unwrap();
+            user
+        });
+        (dir, state, users)
+    }
+
+    // Seperti `setup_state`, tetapi dengan blockchain lengkap yang memberi tahu mempool
+    // setiap blok baru, beserta kunci otoritas untuk membuat blok.
+    fn setup_chain<const N: usize>(balances: [u64; N]) -> (TempDir, Blockchain, Arc<Mempool>, KeyPair, [KeyPair; N]) {
+        let dir = tempdir().unwrap();
+        let mut blockchain = Blockchain::new(dir.path().to_str().unwrap());
+        let mempool = Arc::new(Mempool::new());
+        blockchain.add_observer(mempool.clone());
+        let users = balances.map(|balance| {
+            let user = KeyPair::new();
+            blockchain.state.set_account(&user.public_key_bytes(), &Account::new(balance)).unwrap();
+            user
+        });
+        (dir, blockchain, mempool, KeyPair::new(), users)
+    }
+
+    #[test]
+    fn test_add_valid_transaction() {
+        let (_dir, state, [user1_keys]) = setup_state([1000]);
         let mempool = Mempool::new();
-        let user1_keys = KeyPair::new(); // <-- PERBAIKAN
-        let user2_address: Address = KeyPair::new().public_key_bytes(); // <-- PERBAIKAN
-        let user1_account = Account { balance: 1000, nonce: 0 };
-        state.set_account(&user1_keys.public_key_bytes(), &user1_account).unwrap();
+        let user2_address: Address = KeyPair::new().public_key_bytes();
         let tx = create_test_tx(&user1_keys, user2_address, 100, 0);
 
         let result = mempool.add_transaction(tx.clone(), &state);
         assert!(result.is_ok());
-        assert_eq!(mempool.transactions.lock().unwrap().len(), 1);
-        assert!(mempool.transactions.lock().unwrap().contains(&tx));
+        assert_eq!(mempool.len(), 1);
+        assert!(mempool.contains(&tx.hash()));
     }
 
     #[test]
     fn test_reject_stale_nonce() {
-        let dir = tempdir().unwrap();
-        let state = StateMachine::new(dir.path().to_str().unwrap()).unwrap();
+        let (_dir, state, [user1_keys]) = setup_state([1000]);
         let mempool = Mempool::new();
-        let user1_keys = KeyPair::new(); // <-- PERBAIKAN
-        let user2_address: Address = KeyPair::new().public_key_bytes(); // <-- PERBAIKAN
-        let user1_account = Account { balance: 1000, nonce: 5 };
+        let user2_address: Address = KeyPair::new().public_key_bytes();
+        let user1_account = Account { balance: 1000, staked_amount: 0, nonce: 5, signing_key: None };
         state.set_account(&user1_keys.public_key_bytes(), &user1_account).unwrap();
         let tx = create_test_tx(&user1_keys, user2_address, 100, 0);
 
         let result = mempool.add_transaction(tx, &state);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Nonce sudah usang (replay attack?)");
-        assert_eq!(mempool.transactions.lock().unwrap().len(), 0);
+        assert_eq!(mempool.len(), 0);
     }
 
     #[test]
     fn test_reject_insufficient_balance() {
-        let dir = tempdir().unwrap();
-        let state = StateMachine::new(dir.path().to_str().unwrap()).unwrap();
+        let (_dir, state, [user1_keys]) = setup_state([50]);
         let mempool = Mempool::new();
-        let user1_keys = KeyPair::new(); // <-- PERBAIKAN
-        let user2_address: Address = KeyPair::new().public_key_bytes(); // <-- PERBAIKAN
-        let user1_account = Account { balance: 50, nonce: 0 };
-        state.set_account(&user1_keys.public_key_bytes(), &user1_account).unwrap();
+        let user2_address: Address = KeyPair::new().public_key_bytes();
         let tx = create_test_tx(&user1_keys, user2_address, 100, 0);
 
         let result = mempool.add_transaction(tx, &state);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Saldo tidak cukup");
-        assert_eq!(mempool.transactions.lock().unwrap().len(), 0);
+        assert_eq!(mempool.len(), 0);
     }
 
     #[test]
     fn test_reject_duplicate_transaction() {
-        let dir = tempdir().unwrap();
-        let state = StateMachine::new(dir.path().to_str().unwrap()).unwrap();
+        let (_dir, state, [user1_keys]) = setup_state([1000]);
         let mempool = Mempool::new();
-        let user1_keys = KeyPair::new(); // <-- PERBAIKAN
-        let user2_address: Address = KeyPair::new().public_key_bytes(); // <-- PERBAIKAN
-        let user1_account = Account { balance: 1000, nonce: 0 };
-        state.set_account(&user1_keys.public_key_bytes(), &user1_account).unwrap();
+        let user2_address: Address = KeyPair::new().public_key_bytes();
         let tx = create_test_tx(&user1_keys, user2_address, 100, 0);
 
         let result1 = mempool.add_transaction(tx.clone(), &state);
         assert!(result1.is_ok());
-        assert_eq!(mempool.transactions.lock().unwrap().len(), 1);
+        assert_eq!(mempool.len(), 1);
 
         let result2 = mempool.add_transaction(tx, &state);
         assert!(result2.is_err());
         assert_eq!(result2.unwrap_err(), "Transaksi sudah ada di mempool");
-        assert_eq!(mempool.transactions.lock().unwrap().len(), 1);
+        assert_eq!(mempool.len(), 1);
+    }
+
+    #[test]
+    fn test_get_transactions_prefers_high_fee_in_nonce_order() {
+        let (_dir, state, [spammer, payer]) = setup_state([1000, 1000]);
+        let mempool = Mempool::new();
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        for nonce in 0..3 {
+            mempool.add_transaction(create_test_tx_with_fee(&spammer, recipient, 1, nonce, 1), &state).unwrap();
+        }
+        // Nonce 1 dengan fee tinggi tetap harus menunggu nonce 0 yang ber-fee rendah.
+        mempool.add_transaction(create_test_tx_with_fee(&payer, recipient, 1, 1, 50), &state).unwrap();
+        mempool.add_transaction(create_test_tx_with_fee(&payer, recipient, 1, 0, 5), &state).unwrap();
+
+        let stats = mempool.fee_stats();
+        assert_eq!((stats.pending, stats.min, stats.median, stats.max), (5, 1, 1, 50));
+
+        let picked: Vec<(u64, u64)> = mempool.get_transactions(3).iter().map(|tx| (tx.fee, tx.nonce)).collect();
+        assert_eq!(picked, vec![(5, 0), (50, 1), (1, 0)]);
+        assert_eq!(mempool.fee_stats().pending, 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_future_transactions_wait_for_nonce_gap() {
+        let (_dir, state, [sender]) = setup_state([1000]);
+        let mempool = Mempool::new();
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        mempool.add_transaction(create_test_tx(&sender, recipient, 1, 1), &state).unwrap();
+        mempool.add_transaction(create_test_tx(&sender, recipient, 1, 2), &state).unwrap();
+        assert_eq!((mempool.ready_len(), mempool.future_len()), (0, 2));
+        assert!(mempool.get_transactions(10).is_empty());
+
+        // Nonce 0 mengisi celah sehingga nonce 1 dan 2 ikut menjadi ready.
+        mempool.add_transaction(create_test_tx(&sender, recipient, 1, 0), &state).unwrap();
+        assert_eq!((mempool.ready_len(), mempool.future_len()), (3, 0));
+
+        let nonces: Vec<u64> = mempool.get_transactions(10).iter().map(|tx| tx.nonce).collect();
+        assert_eq!(nonces, vec![0, 1, 2]);
+        assert!(mempool.is_empty());
+
+        // Nonce yang sudah diserahkan ke produsen blok tidak boleh masuk lagi,
+        // meskipun blok belum dikonfirmasi dan nonce akun belum naik.
+        let result = mempool.add_transaction(create_test_tx(&sender, recipient, 2, 1), &state);
+        assert_eq!(result.unwrap_err(), "Nonce sudah usang (replay attack?)");
+    }
+
+    #[test]
+    fn test_replace_by_fee_requires_minimum_bump() {
+        let (_dir, state, [sender]) = setup_state([1000]);
+        let mempool = Mempool::with_config(
+            SignatureCache::default(),
+            MempoolConfig { replacement_fee_bump_percent: 10, ..MempoolConfig::default() }
+        );
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        let original = create_test_tx_with_fee(&sender, recipient, 1, 0, 20);
+        mempool.add_transaction(original.clone(), &state).unwrap();
+
+        // 21 < 20 + 10%, jadi ditolak dan transaksi lama tetap di mempool.
+        let result = mempool.add_transaction(create_test_tx_with_fee(&sender, recipient, 1, 0, 21), &state);
+        assert_eq!(
+            result.unwrap_err(),
+            "Fee pengganti terlalu rendah untuk menggantikan transaksi dengan nonce yang sama"
+        );
+        assert!(mempool.contains(&original.hash()));
+
+        let replacement = create_test_tx_with_fee(&sender, recipient, 1, 0, 22);
+        mempool.add_transaction(replacement.clone(), &state).unwrap();
+        assert_eq!(mempool.len(), 1);
+        assert!(!mempool.contains(&original.hash()));
+        assert!(mempool.contains(&replacement.hash()));
+
+        // Fee nol tetap harus naik minimal 1.
+        assert_eq!(mempool.replacement_fee(0), 1);
+    }
+
+    #[test]
+    fn test_capacity_limits_and_eviction() {
+        let (_dir, state, senders) = setup_state([1000, 1000, 1000]);
+        let config = MempoolConfig { max_transactions: 4, max_per_sender: 2, ..MempoolConfig::default() };
+        let mempool = Mempool::with_config(SignatureCache::default(), config);
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        mempool.add_transaction(create_test_tx_with_fee(&senders[0], recipient, 1, 0, 1), &state).unwrap();
+        mempool.add_transaction(create_test_tx_with_fee(&senders[0], recipient, 1, 1, 2), &state).unwrap();
+        let result = mempool.add_transaction(create_test_tx_with_fee(&senders[0], recipient, 1, 2, 9), &state);
+        assert_eq!(result.unwrap_err(), ERR_SENDER_LIMIT);
+
+        mempool.add_transaction(create_test_tx_with_fee(&senders[1], recipient, 1, 0, 5), &state).unwrap();
+        mempool.add_transaction(create_test_tx_with_fee(&senders[1], recipient, 1, 1, 6), &state).unwrap();
+        assert_eq!(mempool.len(), 4);
+        assert!(mempool.size_bytes() > 0);
+
+        // Penuh: fee minimum naik ke persentil ke-90 dari fee yang menunggu.
+        assert_eq!(mempool.fee_stats().floor, 5);
+        let result = mempool.add_transaction(create_test_tx_with_fee(&senders[2], recipient, 1, 0, 4), &state);
+        assert!(is_capacity_error(result.unwrap_err()));
+
+        // Fee 7 menggeser transaksi terakhir pengirim pertama (fee 2), bukan nonce 0-nya
+        // yang ber-fee 1, agar antrean pengirim itu tetap bersambung.
+        let evicted = create_test_tx_with_fee(&senders[0], recipient, 1, 1, 2);
+        mempool.add_transaction(create_test_tx_with_fee(&senders[2], recipient, 1, 0, 7), &state).unwrap();
+        assert_eq!(mempool.len(), 4);
+        assert!(!mempool.contains(&evicted.hash()));
+        assert_eq!(mempool.ready_len(), 4);
+        assert!(!is_capacity_error("Saldo tidak cukup"));
+    }
+
+    #[test]
+    fn test_block_import_prunes_and_revalidates() {
+        let (_dir, mut blockchain, mempool, authority, [sender, spender]) = setup_chain([1000, 100]);
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        let included = create_test_tx(&sender, recipient, 10, 0);
+        let follow_up = create_test_tx(&sender, recipient, 10, 1);
+        let drained = create_test_tx(&spender, recipient, 80, 0);
+        let unaffordable = create_test_tx(&spender, recipient, 80, 1);
+        for tx in [&included, &follow_up, &unaffordable] {
+            mempool.add_transaction(tx.clone(), &blockchain.state).unwrap();
+        }
+
+        // Blok dari node lain memuat `included` dan transaksi spender yang belum kita lihat.
+        let block = blockchain.create_block(&authority, vec![included.clone(), drained]);
+        assert!(blockchain.add_block(block));
+
+        assert!(!mempool.contains(&included.hash()));
+        assert!(!mempool.contains(&unaffordable.hash()), "Saldo spender tinggal 20");
+        assert!(mempool.contains(&follow_up.hash()));
+        assert_eq!((mempool.len(), mempool.ready_len()), (1, 1));
+        assert_eq!(mempool.get_transactions(10)[0].nonce, 1);
+    }
+
+    #[test]
+    fn test_block_import_checks_cumulative_spend() {
+        let (_dir, mut blockchain, mempool, authority, [sender]) = setup_chain([1000]);
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        let first = create_test_tx(&sender, recipient, 60, 0);
+        let second = create_test_tx(&sender, recipient, 60, 1);
+        let third = create_test_tx(&sender, recipient, 30, 2);
+        for tx in [&first, &second, &third] {
+            mempool.add_transaction(tx.clone(), &blockchain.state).unwrap();
+        }
+
+        // Masing-masing terbayar dari saldo 100, tetapi setelah `first` hanya tersisa 40.
+        blockchain.state.set_account(&sender.public_key_bytes(), &Account::new(100)).unwrap();
+        let block = blockchain.create_block(&authority, vec![]);
+        assert!(blockchain.add_block(block));
+
+        assert!(mempool.contains(&first.hash()));
+        assert_eq!(
+            mempool.status(&second.hash()),
+            Some(TxStatus::Dropped { reason: ERR_INSUFFICIENT_BALANCE.to_string() })
+        );
+        assert_eq!(mempool.status(&third.hash()), Some(TxStatus::Pending { ready: false }));
+    }
+
+    #[test]
+    fn test_in_flight_transactions_return_after_next_block() {
+        let (_dir, mut blockchain, mempool, authority, [sender, other]) = setup_chain([1000, 1000]);
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        let lost = create_test_tx(&sender, recipient, 10, 0);
+        let rejected = create_test_tx(&other, recipient, 10, 0);
+        mempool.add_transaction(lost.clone(), &blockchain.state).unwrap();
+        mempool.add_transaction(rejected.clone(), &blockchain.state).unwrap();
+        assert_eq!(mempool.get_transactions(10).len(), 2);
+        assert_eq!(mempool.status(&lost.hash()), Some(TxStatus::Pending { ready: true }));
+        assert_eq!(mempool.next_nonce(&sender.public_key_bytes(), 0), 1);
+
+        // Produsen blok tidak pernah mengembalikan `lost`, sedangkan `rejected` dilepas
+        // sebagai transaksi yang dibuang. Blok berikutnya mengembalikan yang masih in-flight.
+        mempool.record_dropped(rejected.hash(), "ditolak".to_string());
+        let block = blockchain.create_block(&authority, vec![]);
+        assert!(blockchain.add_block(block));
+
+        assert!(mempool.contains(&lost.hash()));
+        assert!(!mempool.contains(&rejected.hash()));
+        assert_eq!(mempool.ready_len(), 1);
+        let taken = mempool.get_transactions(10);
+        assert_eq!(taken, vec![lost.clone()]);
+
+        // Setelah dikembalikan lewat blok, pengembalian eksplisit yang terlambat tidak menggandakannya.
+        let block = blockchain.create_block(&authority, vec![]);
+        assert!(blockchain.add_block(block));
+        mempool.return_transactions(taken, &blockchain.state);
+        assert_eq!(mempool.len(), 1);
+    }
+
+    #[test]
+    fn test_pending_rotation_key_signs_later_transactions() {
+        let (_dir, mut blockchain, mempool, authority, [old_key]) = setup_chain([1000]);
+        let new_key = KeyPair::new();
+        let sender = old_key.public_key_bytes();
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        let sign_as = |key: &KeyPair, data: TransactionData, nonce: u64| {
+            let mut tx = Transaction {
+                sender,
+                data,
+                fee: 0,
+                nonce,
+                signer: None,
+                signature_scheme: key.scheme,
+                signature: Vec::new(),
+            };
+            tx.signature = key.sign(&tx.message_hash());
+            tx
+        };
+        let transfer = |amount| TransactionData::Transfer { recipient, amount };
+        let rotate = sign_as(&old_key, TransactionData::RotateKey { new_key: new_key.public_key() }, 0);
+        mempool.add_transaction(rotate.clone(), &blockchain.state).unwrap();
+
+        // Rotasi belum ditambang, tetapi transaksi sesudahnya sudah memakai kunci baru.
+        mempool.add_transaction(sign_as(&new_key, transfer(1), 1), &blockchain.state).unwrap();
+        let stale = sign_as(&old_key, transfer(1), 2);
+        assert_eq!(mempool.add_transaction(stale, &blockchain.state), Err(ERR_INVALID_SIGNATURE));
+
+        // Saat rotasi sedang diserahkan ke produsen blok, kuncinya tetap berlaku.
+        assert_eq!(mempool.get_transactions(1), vec![rotate.clone()]);
+        mempool.add_transaction(sign_as(&new_key, transfer(2), 2), &blockchain.state).unwrap();
+
+        let block = blockchain.create_block(&authority, vec![rotate]);
+        assert!(blockchain.add_block(block));
+        assert_eq!(mempool.len(), 2);
+        assert_eq!(mempool.ready_len(), 2);
+    }
+
+    #[test]
+    fn test_journal_restores_valid_transactions() {
+        let dir = tempdir().unwrap();
+        let state = StateMachine::new(dir.path().join("db").to_str().unwrap()).unwrap();
+        let journal_path = dir.path().join("mempool.journal");
+        let sender = KeyPair::new();
+        let spender = KeyPair::new();
+        let recipient: Address = KeyPair::new().public_key_bytes();
+        state.set_account(&sender.public_key_bytes(), &Account::new(1000)).unwrap();
+        state.set_account(&spender.public_key_bytes(), &Account::new(1000)).unwrap();
+
+        let kept = create_test_tx(&sender, recipient, 10, 0);
+        let invalidated = create_test_tx(&spender, recipient, 500, 0);
+        let mempool = Mempool::new();
+        assert_eq!(mempool.open_journal(&journal_path, &state).unwrap(), 0);
+        mempool.add_transaction(kept.clone(), &state).unwrap();
+        mempool.add_transaction(invalidated.clone(), &state).unwrap();
+        assert!(Journal::read(&journal_path).unwrap().is_empty(), "Ditulis saat flush, bukan saat masuk");
+        mempool.flush_journal();
+        assert_eq!(Journal::read(&journal_path).unwrap().len(), 2);
+
+        // Panjang record yang tidak wajar di ujung file dianggap rusak, bukan dialokasikan.
+        let mut file = std::fs::OpenOptions::new().append(true).open(&journal_path).unwrap();
+        std::io::Write::write_all(&mut file, &[0xff, 0xff, 0xff, 0xff, 1, 2, 3]).unwrap();
+
+        // Selama node mati saldo spender habis, jadi transaksinya tidak dipulihkan.
+        state.set_account(&spender.public_key_bytes(), &Account::new(10)).unwrap();
+        let restarted = Mempool::new();
+        assert_eq!(restarted.open_journal(&journal_path, &state).unwrap(), 1);
+        assert!(restarted.contains(&kept.hash()));
+        assert!(!restarted.contains(&invalidated.hash()));
+        assert_eq!(Journal::read(&journal_path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_p2p_transactions_pass_state_checks() {
+        let (_dir, state, [funded]) = setup_state([100]);
+        let mempool = Mempool::new();
+        let unknown = KeyPair::new();
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        // Akun tidak ada atau saldo kurang bisa berarti state kita tertinggal, bukan kesalahan peer.
+        let err = mempool.add_from_p2p(create_test_tx(&unknown, recipient, 1, 0), &state).unwrap_err();
+        assert_eq!(err, ERR_UNKNOWN_SENDER);
+        assert!(!is_peer_fault(err));
+        let err = mempool.add_from_p2p(create_test_tx(&funded, recipient, 500, 0), &state).unwrap_err();
+        assert_eq!(err, ERR_INSUFFICIENT_BALANCE);
+        assert!(!is_peer_fault(err));
+
+        // Tanda tangan dari kunci lain dengan `signer` milik pengirim tidak bisa valid di state mana pun.
+        let mut forged = create_test_tx(&funded, recipient, 1, 0);
+        forged.signer = Some(funded.public_key());
+        forged.signature = unknown.sign(&forged.message_hash());
+        let err = mempool.add_from_p2p(forged, &state).unwrap_err();
+        assert_eq!(err, ERR_SIGNER_MISMATCH);
+        assert!(is_peer_fault(err));
+
+        let mut tampered = create_test_tx(&funded, recipient, 1, 0);
+        tampered.signature.truncate(10);
+        let err = mempool.add_from_p2p(tampered, &state).unwrap_err();
+        assert_eq!(err, ERR_INVALID_SIGNATURE);
+        assert!(is_peer_fault(err));
+
+        let overflowing = create_test_tx_with_fee(&funded, recipient, u64::MAX, 0, 1);
+        let err = mempool.add_from_p2p(overflowing, &state).unwrap_err();
+        assert_eq!(err, ERR_AMOUNT_OVERFLOW);
+        assert!(is_peer_fault(err));
+
+        let tx = create_test_tx(&funded, recipient, 10, 0);
+        mempool.add_from_p2p(tx.clone(), &state).unwrap();
+        let err = mempool.add_from_p2p(tx, &state).unwrap_err();
+        assert_eq!(err, ERR_DUPLICATE);
+        assert!(!is_peer_fault(err));
+        assert_eq!(mempool.len(), 1);
+        for err in [ERR_STALE_NONCE, ERR_FEE_BELOW_MINIMUM, ERR_POOL_FULL, ERR_SENDER_LIMIT, ERR_FEE_FLOOR] {
+            assert!(!is_peer_fault(err));
+        }
+    }
+
+    #[test]
+    fn test_status_tracks_pending_and_dropped() {
+        let (_dir, state, [sender]) = setup_state([1000]);
+        let mempool = Mempool::new();
+        let recipient: Address = KeyPair::new().public_key_bytes();
+
+        let original = create_test_tx_with_fee(&sender, recipient, 1, 0, 1);
+        let future = create_test_tx_with_fee(&sender, recipient, 1, 2, 1);
+        mempool.add_transaction(original.clone(), &state).unwrap();
+        mempool.add_transaction(future.clone(), &state).unwrap();
+        assert_eq!(mempool.status(&original.hash()), Some(TxStatus::Pending { ready: true }));
+        assert_eq!(mempool.status(&future.hash()), Some(TxStatus::Pending { ready: false }));
+
+        let replacement = create_test_tx_with_fee(&sender, recipient, 1, 0, 5);
+        mempool.add_transaction(replacement.clone(), &state).unwrap();
+        assert_eq!(
+            mempool.status(&original.hash()),
+            Some(TxStatus::Dropped { reason: DROP_REPLACED.to_string() })
+        );
+        let pending: Vec<(u64, bool)> = mempool
+            .pending_for(&sender.public_key_bytes())
+            .iter()
+            .map(|(tx, ready)| (tx.nonce, *ready))
+            .collect();
+        assert_eq!(pending, vec![(0, true), (2, false)]);
+
+        let usage = mempool.usage();
+        assert_eq!((usage.count, usage.ready, usage.future), (2, 1, 1));
+        assert_eq!(usage.bytes, mempool.size_bytes());
+        assert_eq!(mempool.status(&[0u8; 32]), None);
+    }
+
+    #[test]
+    fn test_next_nonce_counts_contiguous_pending() {
+        let (_dir, state, [sender]) = setup_state([1000]);
+        let mempool = Mempool::new();
+        let address = sender.public_key_bytes();
+        let recipient: Address = KeyPair::new().public_key_bytes();
+        assert_eq!(mempool.next_nonce(&address, 0), 0);
+
+        for nonce in [0, 1, 3] {
+            mempool.add_transaction(create_test_tx(&sender, recipient, 1, nonce), &state).unwrap();
+        }
+        // Nonce 3 tertahan di balik celah, jadi nonce berikutnya adalah 2.
+        assert_eq!(mempool.next_nonce(&address, 0), 2);
+
+        // Transaksi yang sedang disusun ke blok tetap dihitung.
+        assert_eq!(mempool.get_transactions(2).len(), 2);
+        assert_eq!(mempool.next_nonce(&address, 0), 2);
+    }
+}
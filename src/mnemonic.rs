@@ -0,0 +1,119 @@
+// src/mnemonic.rs
+
+// Frasa mnemonic BIP39 dan penurunan kunci hierarkis untuk dompet.
+// Jalur turunan selalu hardened: m/8017'/skema'/akun'/indeks', mengikuti
+// SLIP-10 (HMAC-SHA512) sehingga alamat yang sama dapat diturunkan ulang
+// dari frasa yang sama di mesin mana pun.
+
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha512;
+
+use crate::crypto::{KeyPair, SignatureScheme};
+
+pub const MNEMONIC_WORDS: usize = 24;
+const DERIVATION_PURPOSE: u32 = 8017;
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+const MASTER_KEY_SALT: &[u8] = b"evice seed";
+
+pub fn generate_mnemonic() -> String {
+    // 24 kata = 256 bit entropi.
+    let mut entropy = [0u8; MNEMONIC_WORDS / 3 * 4];
+    rand::rngs::OsRng.fill_bytes(&mut entropy);
+    Mnemonic::from_entropy(&entropy)
+        .expect("Panjang entropi mnemonic sudah benar")
+        .to_string()
+}
+
+pub fn validate_mnemonic(phrase: &str) -> Result<(), String> {
+    Mnemonic::parse_normalized(phrase.trim())
+        .map(|_| ())
+        .map_err(|e| format!("Mnemonic tidak valid: {}", e))
+}
+
+// Seed BIP39 (PBKDF2-HMAC-SHA512, 2048 iterasi) dari frasa dan passphrase opsional.
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64], String> {
+    let mnemonic = Mnemonic::parse_normalized(phrase.trim()).map_err(|e| format!("Mnemonic tidak valid: {}", e))?;
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC menerima kunci sepanjang apa pun");
+    for part in data {
+        mac.update(part);
+    }
+    let output = mac.finalize().into_bytes();
+    (output[..32].try_into().unwrap(), output[32..].try_into().unwrap())
+}
+
+// Seed 32 byte untuk akun `account` dan indeks alamat `index` pada skema tertentu.
+pub fn derive_account_seed(seed: &[u8; 64], scheme: SignatureScheme, account: u32, index: u32) -> [u8; 32] {
+    let (mut key, mut chain_code) = hmac_sha512(MASTER_KEY_SALT, &[seed]);
+    for level in [DERIVATION_PURPOSE, scheme.tag() as u32, account, index] {
+        let hardened = (level | HARDENED_OFFSET).to_be_bytes();
+        (key, chain_code) = hmac_sha512(&chain_code, &[&[0], &key, &hardened]);
+    }
+    key
+}
+
+pub fn derive_keypair(
+    phrase: &str,
+    passphrase: &str,
+    scheme: SignatureScheme,
+    account: u32,
+    index: u32,
+) -> Result<KeyPair, String> {
+    let seed = mnemonic_to_seed(phrase, passphrase)?;
+    KeyPair::from_seed(scheme, &derive_account_seed(&seed, scheme, account, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_same_mnemonic_gives_same_addresses() {
+        let first = derive_keypair(PHRASE, "", SignatureScheme::Dilithium2, 0, 0).unwrap();
+        let again = derive_keypair(PHRASE, "", SignatureScheme::Dilithium2, 0, 0).unwrap();
+        assert_eq!(first.public_key_bytes(), again.public_key_bytes());
+        assert_eq!(first.private_key_bytes(), again.private_key_bytes());
+
+        let next_index = derive_keypair(PHRASE, "", SignatureScheme::Dilithium2, 0, 1).unwrap();
+        let next_account = derive_keypair(PHRASE, "", SignatureScheme::Dilithium2, 1, 0).unwrap();
+        let with_passphrase = derive_keypair(PHRASE, "rahasia", SignatureScheme::Dilithium2, 0, 0).unwrap();
+        assert_ne!(first.public_key_bytes(), next_index.public_key_bytes());
+        assert_ne!(first.public_key_bytes(), next_account.public_key_bytes());
+        assert_ne!(first.public_key_bytes(), with_passphrase.public_key_bytes());
+
+        let signature = first.sign(b"pesan");
+        assert!(first.public_key().verify(b"pesan", SignatureScheme::Dilithium2, &signature));
+    }
+
+    // Vektor tetap: mengubah jalur turunan atau keygen akan mengubah alamat pengguna.
+    #[test]
+    fn test_derivation_is_stable() {
+        use sha2::{Digest, Sha256};
+        let dilithium = derive_keypair(PHRASE, "", SignatureScheme::Dilithium2, 0, 0).unwrap();
+        assert_eq!(
+            hex::encode(Sha256::digest(dilithium.public_key_bytes())),
+            "de3c3c65e0036bea97d5e8bf3f859f03f5297907190cb6e3bca7f6e46ff1bfb2"
+        );
+        let ed25519 = derive_keypair(PHRASE, "", SignatureScheme::Ed25519, 0, 0).unwrap();
+        assert_eq!(
+            hex::encode(ed25519.public_key().bytes),
+            "3f88ad5806e6ba1f814abf0c5dddbc45fcabcccbd447ca3dfe073ca8bbda1b28"
+        );
+    }
+
+    #[test]
+    fn test_generated_mnemonic_round_trips() {
+        let phrase = generate_mnemonic();
+        assert_eq!(phrase.split_whitespace().count(), MNEMONIC_WORDS);
+        assert!(validate_mnemonic(&phrase).is_ok());
+        assert!(validate_mnemonic("abandon abandon abandon").is_err());
+        assert!(derive_keypair(&phrase, "", SignatureScheme::Ed25519, 0, 0).is_ok());
+    }
+}
@@ -15,7 +15,7 @@ use std::time::Duration;
 use tokio::{select, sync::mpsc};
 
 use crate::blockchain::{Block, Blockchain, ChainMessage};
-use crate::mempool::Mempool;
+use crate::mempool::{self, Mempool};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SyncRequest {
@@ -28,6 +28,21 @@ pub enum SyncResponse {
 }
 
 const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/evice-blockchain/sync/1.0");
+const TOPIC: &str = "evice-blockchain-topic";
+
+// Skor peer gossipsub: hanya pesan tidak valid (P4) yang dihukum. Penalti pengiriman
+// mesh (P3) dimatikan karena lalu lintas topik ini jarang dan tidak menentu.
+fn peer_score_params(topic: &gossipsub::IdentTopic) -> gossipsub::PeerScoreParams {
+    let topic_params = gossipsub::TopicScoreParams {
+        mesh_message_deliveries_weight: 0.0,
+        mesh_failure_penalty_weight: 0.0,
+        invalid_message_deliveries_weight: -10.0,
+        ..Default::default()
+    };
+    let mut params = gossipsub::PeerScoreParams::default();
+    params.topics.insert(topic.hash(), topic_params);
+    params
+}
 
 #[derive(NetworkBehaviour)]
 pub struct AppBehaviour {
@@ -51,10 +66,15 @@ pub async fn run(
         .with_tokio()
         .with_tcp(tcp::Config::default(), noise::Config::new, yamux::Config::default)?
         .with_behaviour(|key| {
-            let gossipsub = gossipsub::Behaviour::new(
+            // Pesan baru diteruskan ke peer lain setelah kita menilainya lewat
+            // `report_message_validation_result`.
+            let config = gossipsub::ConfigBuilder::default().validate_messages().build()?;
+            let mut gossipsub = gossipsub::Behaviour::new(
                 gossipsub::MessageAuthenticity::Signed(key.clone()),
-                gossipsub::Config::default(),
+                config,
             )?;
+            let topic = gossipsub::IdentTopic::new(TOPIC);
+            gossipsub.with_peer_score(peer_score_params(&topic), gossipsub::PeerScoreThresholds::default())?;
             let store = kad::store::MemoryStore::new(local_peer_id);
             let kademlia = kad::Behaviour::new(local_peer_id, store);
             let req_resp = request_response::cbor::Behaviour::new(
@@ -70,7 +90,7 @@ pub async fn run(
         .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
         .build();
 
-    let topic = gossipsub::IdentTopic::new("evice-blockchain-topic");
+    let topic = gossipsub::IdentTopic::new(TOPIC);
     swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
 
     if let Some(addr_str) = bootstrap_node {
@@ -123,22 +143,38 @@ pub async fn run(
                     SwarmEvent::Behaviour(AppBehaviourEvent::Kademlia(event)) => {
                         info!("[KAD] Event: {:?}", event);
                     }
-                    SwarmEvent::Behaviour(AppBehaviourEvent::Gossipsub(gossipsub::Event::Message { message, .. })) => {
-                         match serde_json::from_slice::<ChainMessage>(&message.data) {
+                    SwarmEvent::Behaviour(AppBehaviourEvent::Gossipsub(gossipsub::Event::Message { propagation_source, message_id, message })) => {
+                        let acceptance = match serde_json::from_slice::<ChainMessage>(&message.data) {
                             Ok(ChainMessage::NewBlock(block)) => {
                                 info!("P2P: Menerima blok baru #{} dari jaringan via Gossip.", block.index);
                                 let mut chain = blockchain.lock().unwrap();
-                                if block.index > chain.chain.last().unwrap().index {
-                                     chain.add_block(block);
+                                // Blok yang gagal bisa saja hanya berasal dari fork atau
+                                // ketertinggalan sinkronisasi, jadi tidak dihukum.
+                                if block.index > chain.chain.last().unwrap().index && chain.add_block(block) {
+                                    gossipsub::MessageAcceptance::Accept
+                                } else {
+                                    gossipsub::MessageAcceptance::Ignore
                                 }
                             }
                             Ok(ChainMessage::NewTransaction(tx)) => {
                                 info!("P2P: Menerima transaksi baru dari jaringan via Gossip.");
-                                mempool.add_from_p2p(tx);
+                                let chain = blockchain.lock().unwrap();
+                                match mempool.add_from_p2p(tx, &chain.state) {
+                                    Ok(()) => gossipsub::MessageAcceptance::Accept,
+                                    Err(e) if mempool::is_peer_fault(e) => {
+                                        warn!("P2P: Transaksi tidak valid dari peer {}: {}", propagation_source, e);
+                                        gossipsub::MessageAcceptance::Reject
+                                    }
+                                    Err(_) => gossipsub::MessageAcceptance::Ignore,
+                                }
                             }
                             Err(e) => {
-                                error!("Gagal deserialisasi pesan Gossip: {}", e);
+                                error!("Gagal deserialisasi pesan Gossip dari peer {}: {}", propagation_source, e);
+                                gossipsub::MessageAcceptance::Reject
                             }
+                        };
+                        if let Err(e) = swarm.behaviour_mut().gossipsub.report_message_validation_result(&message_id, &propagation_source, acceptance) {
+                            warn!("P2P: Gagal melaporkan hasil validasi pesan: {:?}", e);
                         }
                     }
                     SwarmEvent::Behaviour(AppBehaviourEvent::ReqResp(event)) => {
@@ -5,7 +5,9 @@ use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
 use crate::blockchain::{Blockchain, ChainMessage, Transaction};
-use crate::mempool::Mempool;
+use crate::crypto::PUBLIC_KEY_SIZE;
+use crate::mempool::{self, Mempool};
+use crate::state::{Address, AssetId, ContractId, HtlcId, NftId, ProposalId};
 
 use log::{info, error, warn};
 
@@ -15,6 +17,18 @@ struct AppState {
     tx_p2p: mpsc::Sender<ChainMessage>,
 }
 
+fn parse_hex_id(id_hex: &str) -> Option<[u8; 32]> {
+    let mut id = [0u8; 32];
+    hex::decode_to_slice(id_hex.trim_start_matches("0x"), &mut id).ok()?;
+    Some(id)
+}
+
+fn parse_address(address_hex: &str) -> Option<Address> {
+    let mut address = [0u8; PUBLIC_KEY_SIZE];
+    hex::decode_to_slice(address_hex.trim_start_matches("0x"), &mut address).ok()?;
+    Some(address)
+}
+
 #[get("/block_count")]
 async fn get_block_count(data: web::Data<AppState>) -> impl Responder {
     let blockchain = data.blockchain.lock().unwrap();
@@ -36,16 +50,382 @@ async fn get_block_by_index(
     }
 }
 
+#[get("/htlc/{id}")]
+async fn get_htlc(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id_hex = path.into_inner();
+    let Some(id): Option<HtlcId> = parse_hex_id(&id_hex) else {
+        return HttpResponse::BadRequest().body("ID HTLC harus berupa 32 byte hex");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_htlc(&id) {
+        Ok(Some(htlc)) => HttpResponse::Ok().json(htlc),
+        Ok(None) => HttpResponse::NotFound().body(format!("HTLC 0x{} tidak ditemukan", hex::encode(id))),
+        Err(e) => {
+            error!("RPC: Gagal membaca HTLC dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/contract/{id}")]
+async fn get_contract(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let id_hex = path.into_inner();
+    let Some(id): Option<ContractId> = parse_hex_id(&id_hex) else {
+        return HttpResponse::BadRequest().body("ID kontrak harus berupa 32 byte hex");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_contract(&id) {
+        Ok(Some(contract)) => HttpResponse::Ok().json(contract),
+        Ok(None) => HttpResponse::NotFound().body(format!("Kontrak 0x{} tidak ditemukan", hex::encode(id))),
+        Err(e) => {
+            error!("RPC: Gagal membaca kontrak dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/contract/{id}/storage/{key}")]
+async fn get_contract_storage(
+    data: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (id_hex, key_hex) = path.into_inner();
+    let Some(id): Option<ContractId> = parse_hex_id(&id_hex) else {
+        return HttpResponse::BadRequest().body("ID kontrak harus berupa 32 byte hex");
+    };
+    let Ok(key) = hex::decode(key_hex.trim_start_matches("0x")) else {
+        return HttpResponse::BadRequest().body("Kunci storage harus berupa hex");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_contract_storage(&id, &key) {
+        Ok(Some(value)) => HttpResponse::Ok().json(hex::encode(value)),
+        Ok(None) => HttpResponse::NotFound().body("Kunci storage tidak ditemukan"),
+        Err(e) => {
+            error!("RPC: Gagal membaca storage kontrak dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/asset/{id}")]
+async fn get_asset(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(id): Option<AssetId> = parse_hex_id(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("ID aset harus berupa 32 byte hex");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_asset(&id) {
+        Ok(Some(asset)) => HttpResponse::Ok().json(asset),
+        Ok(None) => HttpResponse::NotFound().body(format!("Aset 0x{} tidak ditemukan", hex::encode(id))),
+        Err(e) => {
+            error!("RPC: Gagal membaca aset dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/asset/{id}/balance/{address}")]
+async fn get_asset_balance(
+    data: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (id_hex, address_hex) = path.into_inner();
+    let Some(id): Option<AssetId> = parse_hex_id(&id_hex) else {
+        return HttpResponse::BadRequest().body("ID aset harus berupa 32 byte hex");
+    };
+    let Some(address) = parse_address(&address_hex) else {
+        return HttpResponse::BadRequest().body("Alamat tidak valid");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_asset(&id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body(format!("Aset 0x{} tidak ditemukan", hex::encode(id))),
+        Err(e) => {
+            error!("RPC: Gagal membaca aset dari database: {}", e);
+            return HttpResponse::InternalServerError().body("Gagal membaca state");
+        }
+    }
+    match blockchain.state.get_asset_balance(&id, &address) {
+        Ok(balance) => HttpResponse::Ok().json(balance),
+        Err(e) => {
+            error!("RPC: Gagal membaca saldo aset dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/nfts/{address}")]
+async fn get_nfts_by_owner(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(owner) = parse_address(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("Alamat tidak valid");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_nfts_by_owner(&owner) {
+        Ok(ids) => HttpResponse::Ok().json(ids.iter().map(hex::encode).collect::<Vec<_>>()),
+        Err(e) => {
+            error!("RPC: Gagal membaca indeks NFT dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/nft/{id}")]
+async fn get_nft(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(id): Option<NftId> = parse_hex_id(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("ID NFT harus berupa 32 byte hex");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_nft(&id) {
+        Ok(Some(nft)) => HttpResponse::Ok().json(nft),
+        Ok(None) => HttpResponse::NotFound().body(format!("NFT 0x{} tidak ditemukan", hex::encode(id))),
+        Err(e) => {
+            error!("RPC: Gagal membaca NFT dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/nft/{id}/history")]
+async fn get_nft_history(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(id): Option<NftId> = parse_hex_id(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("ID NFT harus berupa 32 byte hex");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_nft(&id) {
+        Ok(Some(nft)) => HttpResponse::Ok().json(nft.history),
+        Ok(None) => HttpResponse::NotFound().body(format!("NFT 0x{} tidak ditemukan", hex::encode(id))),
+        Err(e) => {
+            error!("RPC: Gagal membaca NFT dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/account/{address}")]
+async fn get_account(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(address) = parse_address(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("Alamat tidak valid");
+    };
+    let blockchain = data.blockchain.lock().unwrap();
+
+    // Akun yang belum pernah menerima dana dilaporkan dengan saldo dan nonce nol.
+    match blockchain.state.get_account(&address) {
+        Ok(account) => {
+            let account = account.unwrap_or_else(|| crate::state::Account::new(0));
+            HttpResponse::Ok().json(serde_json::json!({
+                "address": hex::encode(address),
+                "balance": account.balance,
+                "staked_amount": account.staked_amount,
+                "nonce": account.nonce,
+                "signing_key_scheme": account.signing_key.map(|key| key.scheme),
+            }))
+        }
+        Err(e) => {
+            error!("RPC: Gagal membaca akun dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/transaction/{hash}")]
+async fn get_transaction_status(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(tx_hash) = parse_hex_id(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("Hash transaksi tidak valid");
+    };
+
+    if let Some((block_index, tx_index)) = data.blockchain.lock().unwrap().find_transaction(&tx_hash) {
+        return HttpResponse::Ok().json(serde_json::json!({
+            "status": "included",
+            "block": block_index,
+            "index": tx_index,
+        }));
+    }
+    match data.mempool.status(&tx_hash) {
+        Some(status) => HttpResponse::Ok().json(status),
+        None => HttpResponse::NotFound().json(serde_json::json!({ "status": "unknown" })),
+    }
+}
+
+// Nonce untuk transaksi berikutnya, memperhitungkan transaksi yang masih di mempool,
+// agar dompet bisa mengirim beberapa transaksi berturut-turut tanpa menunggu blok.
+#[get("/account/{address}/next_nonce")]
+async fn get_next_nonce(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(address) = parse_address(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("Alamat tidak valid");
+    };
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_account(&address) {
+        Ok(account) => {
+            let account_nonce = account.map_or(0, |account| account.nonce);
+            HttpResponse::Ok().json(serde_json::json!({
+                "address": hex::encode(address),
+                "account_nonce": account_nonce,
+                "next_nonce": data.mempool.next_nonce(&address, account_nonce),
+            }))
+        }
+        Err(e) => {
+            error!("RPC: Gagal membaca akun dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/account/{address}/pending")]
+async fn get_pending_transactions(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(address) = parse_address(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("Alamat tidak valid");
+    };
+    let pending: Vec<_> = data
+        .mempool
+        .pending_for(&address)
+        .into_iter()
+        .map(|(tx, ready)| {
+            serde_json::json!({
+                "hash": hex::encode(tx.hash()),
+                "nonce": tx.nonce,
+                "fee": tx.fee,
+                "ready": ready,
+            })
+        })
+        .collect();
+    HttpResponse::Ok().json(pending)
+}
+
+#[get("/mempool")]
+async fn get_mempool_usage(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.mempool.usage())
+}
+
+#[get("/mempool/fees")]
+async fn get_mempool_fees(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.mempool.fee_stats())
+}
+
+#[get("/name/{name}")]
+async fn resolve_name(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let name = path.into_inner();
+    let blockchain = data.blockchain.lock().unwrap();
+    let current_height = blockchain.chain.last().unwrap().index;
+
+    match blockchain.state.get_name(&name) {
+        Ok(Some(record)) if record.is_active(current_height) => HttpResponse::Ok().json(serde_json::json!({
+            "name": name,
+            "owner": hex::encode(record.owner),
+            "expiry_height": record.expiry_height,
+        })),
+        Ok(_) => HttpResponse::NotFound().body(format!("Nama '{}' tidak terdaftar atau sudah kedaluwarsa", name)),
+        Err(e) => {
+            error!("RPC: Gagal membaca nama dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/params")]
+async fn get_params(data: web::Data<AppState>) -> impl Responder {
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_params() {
+        Ok(params) => HttpResponse::Ok().json(params),
+        Err(e) => {
+            error!("RPC: Gagal membaca parameter chain dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/proposals")]
+async fn get_proposals(data: web::Data<AppState>) -> impl Responder {
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_proposals() {
+        Ok(proposals) => {
+            let proposals: Vec<_> = proposals
+                .into_iter()
+                .map(|(id, proposal)| serde_json::json!({ "id": hex::encode(id), "proposal": proposal }))
+                .collect();
+            HttpResponse::Ok().json(proposals)
+        }
+        Err(e) => {
+            error!("RPC: Gagal membaca proposal dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
+#[get("/proposal/{id}")]
+async fn get_proposal(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let Some(id): Option<ProposalId> = parse_hex_id(&path.into_inner()) else {
+        return HttpResponse::BadRequest().body("ID proposal harus berupa 32 byte hex");
+    };
+
+    let blockchain = data.blockchain.lock().unwrap();
+    match blockchain.state.get_proposal(&id) {
+        Ok(Some(proposal)) => HttpResponse::Ok().json(proposal),
+        Ok(None) => HttpResponse::NotFound().body(format!("Proposal 0x{} tidak ditemukan", hex::encode(id))),
+        Err(e) => {
+            error!("RPC: Gagal membaca proposal dari database: {}", e);
+            HttpResponse::InternalServerError().body("Gagal membaca state")
+        }
+    }
+}
+
 #[post("/transaction")]
 async fn submit_transaction(
     data: web::Data<AppState>,
     tx: web::Json<Transaction>,
 ) -> impl Responder {
     let transaction = tx.into_inner();
-    
-    let state = &data.blockchain.lock().unwrap().state;
 
-    match data.mempool.add_transaction(transaction.clone(), state) {
+    let result = {
+        let chain = data.blockchain.lock().unwrap();
+        data.mempool.add_transaction(transaction.clone(), &chain.state)
+    };
+    // Jurnal ditulis ke disk setelah lock blockchain dilepas.
+    data.mempool.flush_journal();
+
+    match result {
         Ok(_) => {
             info!("RPC: Menerima transaksi valid, menyiarkan ke P2P.");
             if let Err(e) = data.tx_p2p.send(ChainMessage::NewTransaction(transaction)).await {
@@ -54,6 +434,12 @@ async fn submit_transaction(
             }
             HttpResponse::Ok().json("Transaksi diterima dan disiarkan")
         }
+        // Mempool penuh bukan kesalahan transaksi: klien dapat mencoba lagi nanti
+        // atau dengan fee lebih tinggi.
+        Err(e) if mempool::is_capacity_error(e) => {
+            warn!("RPC: Transaksi ditolak karena kapasitas mempool: {}", e);
+            HttpResponse::ServiceUnavailable().json(serde_json::json!({ "error": "mempool_full", "message": e }))
+        }
         Err(e) => {
             warn!("RPC: Menerima transaksi tidak valid: {}", e);
             HttpResponse::BadRequest().body(e.to_string())
@@ -69,7 +455,7 @@ pub async fn run(
 ) -> std::io::Result<()> {
     let server_addr = format!("127.0.0.1:{}", port);
     info!("Menjalankan server RPC di http://{}", server_addr);
-    info!("Endpoint tersedia: GET /block_count, GET /block/{{index}}, POST /transaction");
+    info!("Endpoint tersedia: GET /block_count, GET /block/{{index}}, GET /htlc/{{id}}, GET /contract/{{id}}, GET /contract/{{id}}/storage/{{key}}, GET /asset/{{id}}, GET /asset/{{id}}/balance/{{address}}, GET /nfts/{{address}}, GET /nft/{{id}}, GET /nft/{{id}}/history, GET /account/{{address}}, GET /account/{{address}}/next_nonce, GET /account/{{address}}/pending, GET /transaction/{{hash}}, GET /mempool, GET /mempool/fees, GET /name/{{name}}, GET /params, GET /proposals, GET /proposal/{{id}}, POST /transaction");
 
     let app_data = web::Data::new(AppState {
         blockchain,
@@ -82,6 +468,24 @@ pub async fn run(
             .app_data(app_data.clone())
             .service(get_block_count)
             .service(get_block_by_index)
+            .service(get_htlc)
+            .service(get_contract)
+            .service(get_contract_storage)
+            .service(get_asset)
+            .service(get_asset_balance)
+            .service(get_nfts_by_owner)
+            .service(get_nft)
+            .service(get_nft_history)
+            .service(get_account)
+            .service(get_transaction_status)
+            .service(get_next_nonce)
+            .service(get_pending_transactions)
+            .service(get_mempool_usage)
+            .service(get_mempool_fees)
+            .service(resolve_name)
+            .service(get_params)
+            .service(get_proposals)
+            .service(get_proposal)
             .service(submit_transaction)
     })
     .bind(server_addr)?
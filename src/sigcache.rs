@@ -0,0 +1,131 @@
+// src/sigcache.rs
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_SIGNATURE_CACHE_CAPACITY: usize = 50_000;
+
+// Cache verifikasi tanda tangan yang dibagi antara mempool dan import blok.
+// Kunci cache adalah `Transaction::hash`, nilainya sidik jari kunci publik yang
+// dipakai saat verifikasi berhasil, sehingga rotasi kunci membatalkan entri lama.
+#[derive(Clone)]
+pub struct SignatureCache {
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+struct CacheInner {
+    capacity: usize,
+    // Hash transaksi ke (sidik jari kunci, nomor urut penyisipan).
+    entries: HashMap<[u8; 32], ([u8; 32], u64)>,
+    // Urutan penyisipan untuk eviction FIFO. Record yang nomor urutnya tidak lagi cocok
+    // dengan `entries` (sudah dihapus atau disisipkan ulang) dilewati saat eviction.
+    order: VecDeque<([u8; 32], u64)>,
+    next_seq: u64,
+}
+
+impl CacheInner {
+    fn is_live(&self, tx_hash: &[u8; 32], seq: u64) -> bool {
+        self.entries.get(tx_hash).is_some_and(|(_, live_seq)| *live_seq == seq)
+    }
+}
+
+impl SignatureCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CacheInner {
+                capacity,
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+                next_seq: 0,
+            })),
+        }
+    }
+
+    pub fn contains(&self, tx_hash: &[u8; 32], key_fingerprint: &[u8; 32]) -> bool {
+        self.inner.lock().unwrap().entries.get(tx_hash).map(|(fingerprint, _)| fingerprint) == Some(key_fingerprint)
+    }
+
+    pub fn insert(&self, tx_hash: [u8; 32], key_fingerprint: [u8; 32]) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return;
+        }
+        if let Some(entry) = inner.entries.get_mut(&tx_hash) {
+            entry.0 = key_fingerprint;
+            return;
+        }
+        while inner.entries.len() >= inner.capacity {
+            let Some((oldest, seq)) = inner.order.pop_front() else { break };
+            if inner.is_live(&oldest, seq) {
+                inner.entries.remove(&oldest);
+            }
+        }
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner.entries.insert(tx_hash, (key_fingerprint, seq));
+        inner.order.push_back((tx_hash, seq));
+        // Record basi dari `remove` dibuang sesekali agar `order` tetap terbatas.
+        if inner.order.len() > inner.capacity * 2 {
+            let mut order = std::mem::take(&mut inner.order);
+            order.retain(|(hash, seq)| inner.is_live(hash, *seq));
+            inner.order = order;
+        }
+    }
+
+    pub fn remove(&self, tx_hash: &[u8; 32]) {
+        self.inner.lock().unwrap().entries.remove(tx_hash);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for SignatureCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIGNATURE_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_is_bounded_and_checks_key() {
+        let cache = SignatureCache::new(2);
+        cache.insert([1; 32], [9; 32]);
+        cache.insert([2; 32], [9; 32]);
+        cache.insert([3; 32], [9; 32]);
+
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&[1; 32], &[9; 32]));
+        assert!(cache.contains(&[3; 32], &[9; 32]));
+        assert!(!cache.contains(&[3; 32], &[8; 32]));
+
+        cache.remove(&[3; 32]);
+        assert!(!cache.contains(&[3; 32], &[9; 32]));
+    }
+
+    #[test]
+    fn test_removed_entry_does_not_evict_its_replacement() {
+        let cache = SignatureCache::new(2);
+        cache.insert([1; 32], [9; 32]);
+        cache.remove(&[1; 32]);
+        cache.insert([1; 32], [9; 32]);
+        cache.insert([2; 32], [9; 32]);
+        assert!(cache.contains(&[1; 32], &[9; 32]));
+        assert!(cache.contains(&[2; 32], &[9; 32]));
+
+        // Sisip-hapus berulang tidak membuat `order` tumbuh tanpa batas.
+        for i in 3..100u8 {
+            cache.insert([i; 32], [9; 32]);
+            cache.remove(&[i; 32]);
+        }
+        assert!(cache.inner.lock().unwrap().order.len() <= 4);
+    }
+}
@@ -3,32 +3,538 @@
 use bincode;
 use rocksdb::{Options, DB};
 use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use sha2::{Digest, Sha256};
 
 use crate::blockchain::TransactionData;
+use crate::contract::{self, Contract, ExecutionContext};
 use crate::blockchain::Transaction;
-use crate::crypto::PUBLIC_KEY_SIZE;
+use crate::crypto::{self, TaggedPublicKey, PUBLIC_KEY_SIZE};
 
 // --- TAMBAHAN: Impor makro log ---
 use log::{info, warn};
 
 pub type Address = [u8; PUBLIC_KEY_SIZE];
+pub type HtlcId = [u8; 32];
+pub type ContractId = [u8; 32];
+pub type AssetId = [u8; 32];
+pub type NftId = [u8; 32];
+pub type ProposalId = [u8; 32];
+
+pub const DEFAULT_MAX_BLOCK_TRANSACTIONS: u64 = 10;
+pub const DEFAULT_BLOCK_INTERVAL_SECS: u64 = 10;
+pub const DEFAULT_MIN_FEE: u64 = 0;
+// Jarak minimum antara blok proposal dan tinggi aktivasinya, sebagai masa voting.
+pub const MIN_VOTING_PERIOD: u64 = 10;
+// Proposal hanya sah jika stake yang memberikan suara mencapai persentase ini dari total
+// stake, dan stake yang setuju melebihi separuh total stake (bukan hanya separuh suara).
+pub const GOVERNANCE_QUORUM_PERCENT: u64 = 67;
+
+pub const NAME_SUFFIX: &str = ".evc";
+pub const MIN_NAME_LABEL_LEN: usize = 3;
+pub const MAX_NAME_LABEL_LEN: usize = 32;
+// Biaya registrasi/perpanjangan nama dibakar, di luar biaya transaksi.
+pub const NAME_REGISTRATION_FEE: u64 = 100;
+pub const NAME_REGISTRATION_PERIOD: u64 = 100_000;
+
+pub const MAX_ASSET_SYMBOL_LEN: usize = 12;
+pub const MAX_ASSET_DECIMALS: u8 = 18;
+
+const HTLC_PREFIX: &[u8] = b"htlc:";
+const CONTRACT_PREFIX: &[u8] = b"contract:";
+const CONTRACT_STORAGE_PREFIX: &[u8] = b"cstore:";
+const ASSET_PREFIX: &[u8] = b"asset:";
+const ASSET_BALANCE_PREFIX: &[u8] = b"asset_bal:";
+const NFT_PREFIX: &[u8] = b"nft:";
+const NFT_OWNER_PREFIX: &[u8] = b"nft_owner:";
+const NAME_PREFIX: &[u8] = b"name:";
+const PARAMS_KEY: &[u8] = b"chain_params";
+const TOTAL_STAKE_KEY: &[u8] = b"total_stake";
+const PROPOSAL_PREFIX: &[u8] = b"proposal:";
+const VOTE_PREFIX: &[u8] = b"vote:";
+const ACTIVATION_PREFIX: &[u8] = b"gov_activation:";
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Account {
     pub balance: u64,
     pub staked_amount: u64,
     pub nonce: u64,
+    // Kunci publik hasil rotasi. `None` berarti kunci bawaan alamat (lihat `Transaction::signing_key`).
+    pub signing_key: Option<TaggedPublicKey>,
+}
+
+// Nilai `Account::signing_key` setelah `RotateKey`. Kembali ke kunci bawaan alamat tidak perlu disimpan.
+pub fn rotated_signing_key(address: &Address, new_key: &TaggedPublicKey) -> Option<TaggedPublicKey> {
+    if crypto::derive_address(new_key) == *address {
+        None
+    } else {
+        Some(new_key.clone())
+    }
+}
+
+// Format akun sebelum rotasi kunci, agar database lama tetap bisa dibaca.
+#[derive(Deserialize)]
+struct LegacyAccount {
+    balance: u64,
+    staked_amount: u64,
+    nonce: u64,
 }
 
 impl Account {
     pub fn new(balance: u64) -> Self {
-        Self { balance, staked_amount: 0, nonce: 0 }
+        Self { balance, staked_amount: 0, nonce: 0, signing_key: None }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum HtlcStatus {
+    Locked,
+    Claimed { preimage: Vec<u8> },
+    Refunded,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Htlc {
+    #[serde(with = "serde_bytes")]
+    pub sender: Address,
+    #[serde(with = "serde_bytes")]
+    pub recipient: Address,
+    pub amount: u64,
+    pub hashlock: [u8; 32],
+    pub timeout_height: u64,
+    pub status: HtlcStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Asset {
+    #[serde(with = "serde_bytes")]
+    pub issuer: Address,
+    pub symbol: String,
+    pub decimals: u8,
+    pub max_supply: u64,
+    pub total_supply: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum NftEventKind {
+    Minted,
+    Transferred {
+        #[serde(with = "serde_bytes")]
+        from: Address,
+        #[serde(with = "serde_bytes")]
+        to: Address,
+    },
+    Burned,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NftEvent {
+    pub block_height: u64,
+    pub kind: NftEventKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Nft {
+    #[serde(with = "serde_bytes")]
+    pub creator: Address,
+    #[serde(with = "serde_bytes")]
+    pub owner: Address,
+    pub metadata_hash: [u8; 32],
+    pub burned: bool,
+    pub history: Vec<NftEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NameRecord {
+    #[serde(with = "serde_bytes")]
+    pub owner: Address,
+    pub expiry_height: u64,
+}
+
+impl NameRecord {
+    pub fn is_active(&self, block_height: u64) -> bool {
+        block_height < self.expiry_height
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChainParams {
+    pub max_block_transactions: u64,
+    pub min_fee: u64,
+    pub block_interval_secs: u64,
+    // Kosong berarti belum ada set otoritas yang ditetapkan: blok dari otoritas mana pun diterima.
+    pub authorities: Vec<Vec<u8>>,
+}
+
+impl Default for ChainParams {
+    fn default() -> Self {
+        Self {
+            max_block_transactions: DEFAULT_MAX_BLOCK_TRANSACTIONS,
+            min_fee: DEFAULT_MIN_FEE,
+            block_interval_secs: DEFAULT_BLOCK_INTERVAL_SECS,
+            authorities: Vec::new(),
+        }
+    }
+}
+
+impl ChainParams {
+    pub fn is_authority(&self, address: &Address) -> bool {
+        self.authorities.is_empty() || self.authorities.iter().any(|a| a.as_slice() == address)
+    }
+
+    // Menolak perubahan yang akan membuat chain tidak bisa berjalan atau terbuka untuk
+    // siapa saja. Diperiksa saat proposal dibuat dan lagi saat aktivasi, karena set
+    // otoritas bisa berubah di antaranya.
+    fn check_change(&self, change: &ParamChange) -> Result<(), String> {
+        match change {
+            ParamChange::MaxBlockTransactions(0) | ParamChange::BlockIntervalSecs(0) => {
+                Err("STATE: Ukuran blok dan interval blok harus lebih dari nol".to_string())
+            }
+            // Set kosong berarti blok dari produsen mana pun diterima.
+            ParamChange::RemoveAuthority(address)
+                if self.authorities.iter().all(|a| a.as_slice() == address) =>
+            {
+                Err("STATE: Otoritas terakhir tidak dapat dihapus".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(&mut self, change: &ParamChange) {
+        match change {
+            ParamChange::MaxBlockTransactions(value) => self.max_block_transactions = *value,
+            ParamChange::MinFee(value) => self.min_fee = *value,
+            ParamChange::BlockIntervalSecs(value) => self.block_interval_secs = *value,
+            ParamChange::AddAuthority(address) => {
+                if !self.authorities.iter().any(|a| a.as_slice() == address) {
+                    self.authorities.push(address.to_vec());
+                }
+            }
+            ParamChange::RemoveAuthority(address) => self.authorities.retain(|a| a.as_slice() != address),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParamChange {
+    MaxBlockTransactions(u64),
+    MinFee(u64),
+    BlockIntervalSecs(u64),
+    AddAuthority(#[serde(with = "serde_bytes")] Address),
+    RemoveAuthority(#[serde(with = "serde_bytes")] Address),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Voting,
+    Activated,
+    Rejected,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Proposal {
+    #[serde(with = "serde_bytes")]
+    pub proposer: Address,
+    pub change: ParamChange,
+    pub created_height: u64,
+    pub activation_height: u64,
+    pub yes_stake: u64,
+    pub no_stake: u64,
+    pub status: ProposalStatus,
+}
+
+// HashMap yang bisa mencatat nilai lama setiap kunci yang ditimpa. Selama
+// pencatatan aktif (`begin`), `rollback` mengembalikan hanya kunci yang berubah,
+// jadi satu transaksi yang gagal bisa dibatalkan tanpa menyalin seluruh map.
+pub struct UndoMap<K, V> {
+    map: HashMap<K, V>,
+    undo: Option<Vec<(K, Option<V>)>>,
+}
+
+impl<K, V> Default for UndoMap<K, V> {
+    fn default() -> Self {
+        Self { map: HashMap::new(), undo: None }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> UndoMap<K, V> {
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, K, V> {
+        self.map.iter()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        match &mut self.undo {
+            Some(undo) => {
+                let old = self.map.insert(key.clone(), value);
+                undo.push((key, old));
+            }
+            None => {
+                self.map.insert(key, value);
+            }
+        }
+    }
+
+    fn begin(&mut self) {
+        self.undo = Some(Vec::new());
+    }
+
+    fn rollback(&mut self) {
+        for (key, old) in self.undo.take().unwrap_or_default().into_iter().rev() {
+            match old {
+                Some(value) => self.map.insert(key, value),
+                None => self.map.remove(&key),
+            };
+        }
+    }
+
+    fn release(&mut self) {
+        self.undo = None;
+    }
+}
+
+impl<K, V> IntoIterator for UndoMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::collections::hash_map::IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+// Himpunan dengan pencatatan yang sama seperti `UndoMap`.
+pub struct UndoSet<K>(UndoMap<K, ()>);
+
+impl<K> Default for UndoSet<K> {
+    fn default() -> Self {
+        Self(UndoMap::default())
+    }
+}
+
+impl<K: Hash + Eq + Clone> UndoSet<K> {
+    pub fn contains(&self, key: &K) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn insert(&mut self, key: K) {
+        self.0.insert(key, ());
+    }
+}
+
+impl<K> IntoIterator for UndoSet<K> {
+    type Item = K;
+    type IntoIter = std::collections::hash_map::IntoKeys<K, ()>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.map.into_keys()
     }
 }
 
+// Perubahan state selama validasi satu blok. Hanya ditulis ke database
+// lewat `StateMachine::commit` jika seluruh transaksi di blok valid.
+#[derive(Default)]
+pub struct BlockState {
+    pub accounts: UndoMap<Address, Account>,
+    pub htlcs: UndoMap<HtlcId, Htlc>,
+    pub contracts: UndoMap<ContractId, Contract>,
+    pub contract_storage: UndoMap<(ContractId, Vec<u8>), Vec<u8>>,
+    pub assets: UndoMap<AssetId, Asset>,
+    pub asset_balances: UndoMap<(AssetId, Address), u64>,
+    pub nfts: UndoMap<NftId, Nft>,
+    // Indeks pemilik NFT: true untuk ditambahkan, false untuk dihapus.
+    pub nft_owner_index: UndoMap<(Address, NftId), bool>,
+    pub names: UndoMap<String, NameRecord>,
+    pub params: Option<ChainParams>,
+    pub proposals: UndoMap<ProposalId, Proposal>,
+    pub votes: UndoSet<(ProposalId, Address)>,
+    pub activations: UndoSet<(u64, ProposalId)>,
+    pub total_stake: Option<u64>,
+    saved_scalars: Option<(Option<ChainParams>, Option<u64>)>,
+}
+
+impl BlockState {
+    // Mulai mencatat perubahan satu transaksi; diakhiri `rollback` atau `release`.
+    pub fn begin(&mut self) {
+        self.saved_scalars = Some((self.params.clone(), self.total_stake));
+        self.for_each_map(|map| map.begin());
+    }
+
+    // Membatalkan semua perubahan sejak `begin`.
+    pub fn rollback(&mut self) {
+        if let Some((params, total_stake)) = self.saved_scalars.take() {
+            self.params = params;
+            self.total_stake = total_stake;
+        }
+        self.for_each_map(|map| map.rollback());
+    }
+
+    // Mempertahankan perubahan sejak `begin` dan berhenti mencatat.
+    pub fn release(&mut self) {
+        self.saved_scalars = None;
+        self.for_each_map(|map| map.release());
+    }
+
+    fn for_each_map(&mut self, mut f: impl FnMut(&mut dyn Undo)) {
+        f(&mut self.accounts);
+        f(&mut self.htlcs);
+        f(&mut self.contracts);
+        f(&mut self.contract_storage);
+        f(&mut self.assets);
+        f(&mut self.asset_balances);
+        f(&mut self.nfts);
+        f(&mut self.nft_owner_index);
+        f(&mut self.names);
+        f(&mut self.proposals);
+        f(&mut self.votes.0);
+        f(&mut self.activations.0);
+    }
+}
+
+trait Undo {
+    fn begin(&mut self);
+    fn rollback(&mut self);
+    fn release(&mut self);
+}
+
+impl<K: Hash + Eq + Clone, V> Undo for UndoMap<K, V> {
+    fn begin(&mut self) {
+        UndoMap::begin(self)
+    }
+
+    fn rollback(&mut self) {
+        UndoMap::rollback(self)
+    }
+
+    fn release(&mut self) {
+        UndoMap::release(self)
+    }
+}
+
+fn htlc_key(id: &HtlcId) -> Vec<u8> {
+    let mut key = HTLC_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key
+}
+
+fn contract_key(id: &ContractId) -> Vec<u8> {
+    let mut key = CONTRACT_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key
+}
+
+pub(crate) fn contract_storage_key(id: &ContractId, storage_key: &[u8]) -> Vec<u8> {
+    let mut key = CONTRACT_STORAGE_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key.extend_from_slice(storage_key);
+    key
+}
+
+fn asset_key(id: &AssetId) -> Vec<u8> {
+    let mut key = ASSET_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key
+}
+
+fn asset_balance_key(id: &AssetId, address: &Address) -> Vec<u8> {
+    let mut key = ASSET_BALANCE_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key.extend_from_slice(address);
+    key
+}
+
+fn nft_key(id: &NftId) -> Vec<u8> {
+    let mut key = NFT_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key
+}
+
+fn nft_owner_prefix(owner: &Address) -> Vec<u8> {
+    let mut key = NFT_OWNER_PREFIX.to_vec();
+    key.extend_from_slice(owner);
+    key
+}
+
+fn nft_owner_key(owner: &Address, id: &NftId) -> Vec<u8> {
+    let mut key = nft_owner_prefix(owner);
+    key.extend_from_slice(id);
+    key
+}
+
+fn name_key(name: &str) -> Vec<u8> {
+    let mut key = NAME_PREFIX.to_vec();
+    key.extend_from_slice(name.as_bytes());
+    key
+}
+
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let label = name
+        .strip_suffix(NAME_SUFFIX)
+        .ok_or_else(|| format!("Nama harus berakhiran '{}'", NAME_SUFFIX))?;
+    if label.len() < MIN_NAME_LABEL_LEN || label.len() > MAX_NAME_LABEL_LEN {
+        return Err(format!("Panjang nama harus {}-{} karakter sebelum '{}'", MIN_NAME_LABEL_LEN, MAX_NAME_LABEL_LEN, NAME_SUFFIX));
+    }
+    let valid_chars = label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid_chars || label.starts_with('-') || label.ends_with('-') {
+        return Err("Nama hanya boleh berisi huruf kecil, angka dan '-' (tidak di awal/akhir)".to_string());
+    }
+    Ok(())
+}
+
+fn proposal_key(id: &ProposalId) -> Vec<u8> {
+    let mut key = PROPOSAL_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key
+}
+
+fn vote_key(id: &ProposalId, voter: &Address) -> Vec<u8> {
+    let mut key = VOTE_PREFIX.to_vec();
+    key.extend_from_slice(id);
+    key.extend_from_slice(voter);
+    key
+}
+
+fn activation_prefix(height: u64) -> Vec<u8> {
+    let mut key = ACTIVATION_PREFIX.to_vec();
+    key.extend_from_slice(&height.to_be_bytes());
+    key
+}
+
+fn charge_fee(account: &mut Account, fee: u64) -> Result<(), String> {
+    if account.balance < fee {
+        return Err(format!("STATE: Saldo tidak cukup untuk biaya (butuh {}, punya {})", fee, account.balance));
+    }
+    account.balance -= fee;
+    Ok(())
+}
+
+// Biaya pendaftaran atau perpanjangan nama ditambah biaya transaksi.
+fn name_fee(tx: &Transaction) -> Result<u64, String> {
+    NAME_REGISTRATION_FEE
+        .checked_add(tx.fee)
+        .ok_or_else(|| "STATE: Biaya nama + biaya transaksi melebihi batas".to_string())
+}
+
 pub struct StateMachine {
-    pub db: DB,
+    pub db: Arc<DB>,
 }
 
 impl StateMachine {
@@ -36,13 +542,24 @@ impl StateMachine {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         let db = DB::open(&opts, path)?;
-        Ok(Self { db })
+        Ok(Self { db: Arc::new(db) })
     }
 
     pub fn get_account(&self, address: &Address) -> Result<Option<Account>, bincode::Error> {
         match self.db.get(address) {
             Ok(Some(encoded_account)) => {
-                let account: Account = bincode::deserialize(&encoded_account)?;
+                let account = match bincode::deserialize::<Account>(&encoded_account) {
+                    Ok(account) => account,
+                    Err(e) => {
+                        let legacy: LegacyAccount = bincode::deserialize(&encoded_account).map_err(|_| e)?;
+                        Account {
+                            balance: legacy.balance,
+                            staked_amount: legacy.staked_amount,
+                            nonce: legacy.nonce,
+                            signing_key: None,
+                        }
+                    }
+                };
                 Ok(Some(account))
             }
             Ok(None) => Ok(None),
@@ -60,61 +577,823 @@ impl StateMachine {
         Ok(())
     }
 
+    pub fn set_params(&self, params: &ChainParams) -> Result<(), bincode::Error> {
+        let encoded_params = bincode::serialize(params)?;
+        self.db
+            .put(PARAMS_KEY, encoded_params)
+            .map_err(|e| bincode::Error::new(bincode::ErrorKind::Custom(e.to_string())))?;
+        Ok(())
+    }
+
+    // Kunci hasil rotasi yang terdaftar untuk `address`, jika ada.
+    pub fn signing_key(&self, address: &Address) -> Result<Option<TaggedPublicKey>, bincode::Error> {
+        Ok(self.get_account(address)?.and_then(|account| account.signing_key))
+    }
+
+    pub fn get_htlc(&self, id: &HtlcId) -> Result<Option<Htlc>, bincode::Error> {
+        match self.db.get(htlc_key(id)) {
+            Ok(Some(encoded_htlc)) => Ok(Some(bincode::deserialize(&encoded_htlc)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    pub fn get_contract(&self, id: &ContractId) -> Result<Option<Contract>, bincode::Error> {
+        match self.db.get(contract_key(id)) {
+            Ok(Some(encoded_contract)) => Ok(Some(bincode::deserialize(&encoded_contract)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    pub fn get_contract_storage(&self, id: &ContractId, storage_key: &[u8]) -> Result<Option<Vec<u8>>, rocksdb::Error> {
+        self.db.get(contract_storage_key(id, storage_key))
+    }
+
+    pub fn get_asset(&self, id: &AssetId) -> Result<Option<Asset>, bincode::Error> {
+        match self.db.get(asset_key(id)) {
+            Ok(Some(encoded_asset)) => Ok(Some(bincode::deserialize(&encoded_asset)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    pub fn get_asset_balance(&self, id: &AssetId, address: &Address) -> Result<u64, bincode::Error> {
+        match self.db.get(asset_balance_key(id, address)) {
+            Ok(Some(encoded_balance)) => bincode::deserialize(&encoded_balance),
+            Ok(None) => Ok(0),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    pub fn get_nft(&self, id: &NftId) -> Result<Option<Nft>, bincode::Error> {
+        match self.db.get(nft_key(id)) {
+            Ok(Some(encoded_nft)) => Ok(Some(bincode::deserialize(&encoded_nft)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    pub fn get_nfts_by_owner(&self, owner: &Address) -> Result<Vec<NftId>, rocksdb::Error> {
+        let prefix = nft_owner_prefix(owner);
+        let mut ids = Vec::new();
+        for item in self.db.prefix_iterator(&prefix) {
+            let (key, _) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            if let Ok(id) = NftId::try_from(&key[prefix.len()..]) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    pub fn get_name(&self, name: &str) -> Result<Option<NameRecord>, bincode::Error> {
+        match self.db.get(name_key(name)) {
+            Ok(Some(encoded_record)) => Ok(Some(bincode::deserialize(&encoded_record)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    // Mengembalikan alamat pemilik nama jika nama terdaftar dan belum kedaluwarsa.
+    pub fn resolve_name(&self, name: &str, block_height: u64) -> Result<Option<Address>, bincode::Error> {
+        Ok(self
+            .get_name(name)?
+            .filter(|record| record.is_active(block_height))
+            .map(|record| record.owner))
+    }
+
+    pub fn get_params(&self) -> Result<ChainParams, bincode::Error> {
+        match self.db.get(PARAMS_KEY) {
+            Ok(Some(encoded_params)) => bincode::deserialize(&encoded_params),
+            Ok(None) => Ok(ChainParams::default()),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    // Total stake seluruh akun. Database lama yang belum menyimpan nilainya dihitung dari
+    // seluruh akun dan hasilnya langsung disimpan, jadi pemindaian hanya terjadi sekali.
+    // Dipanggil hanya saat memproses blok, sehingga tidak berlomba dengan `commit`.
+    pub fn get_total_stake(&self) -> Result<u64, bincode::Error> {
+        let to_error = |e: rocksdb::Error| bincode::Error::new(bincode::ErrorKind::Custom(e.to_string()));
+        if let Some(encoded) = self.db.get(TOTAL_STAKE_KEY).map_err(to_error)? {
+            let bytes: [u8; 8] = encoded
+                .as_slice()
+                .try_into()
+                .map_err(|_| bincode::Error::new(bincode::ErrorKind::Custom("total stake rusak".to_string())))?;
+            return Ok(u64::from_le_bytes(bytes));
+        }
+        let mut total: u64 = 0;
+        for item in self.db.iterator(rocksdb::IteratorMode::Start) {
+            let (key, _) = item.map_err(to_error)?;
+            if let Ok(address) = Address::try_from(&key[..]) {
+                if let Some(account) = self.get_account(&address)? {
+                    total = total.saturating_add(account.staked_amount);
+                }
+            }
+        }
+        self.db.put(TOTAL_STAKE_KEY, total.to_le_bytes()).map_err(to_error)?;
+        info!("STATE: Total stake {} dihitung ulang dari seluruh akun dan disimpan.", total);
+        Ok(total)
+    }
+
+    pub fn get_proposal(&self, id: &ProposalId) -> Result<Option<Proposal>, bincode::Error> {
+        match self.db.get(proposal_key(id)) {
+            Ok(Some(encoded_proposal)) => Ok(Some(bincode::deserialize(&encoded_proposal)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(bincode::Error::new(bincode::ErrorKind::Custom(
+                e.to_string(),
+            ))),
+        }
+    }
+
+    pub fn get_proposals(&self) -> Result<Vec<(ProposalId, Proposal)>, bincode::Error> {
+        let mut proposals = Vec::new();
+        for item in self.db.prefix_iterator(PROPOSAL_PREFIX) {
+            let (key, value) = item.map_err(|e| bincode::Error::new(bincode::ErrorKind::Custom(e.to_string())))?;
+            if !key.starts_with(PROPOSAL_PREFIX) {
+                break;
+            }
+            if let Ok(id) = ProposalId::try_from(&key[PROPOSAL_PREFIX.len()..]) {
+                proposals.push((id, bincode::deserialize(&value)?));
+            }
+        }
+        Ok(proposals)
+    }
+
+    fn has_voted(&self, id: &ProposalId, voter: &Address) -> Result<bool, String> {
+        self.db
+            .get(vote_key(id, voter))
+            .map(|vote| vote.is_some())
+            .map_err(|e| format!("STATE: Gagal membaca database vote: {}", e))
+    }
+
+    pub fn commit(&self, block_state: BlockState) -> Result<(), String> {
+        let mut batch = rocksdb::WriteBatch::default();
+        for (address, account) in block_state.accounts {
+            let encoded_account = bincode::serialize(&account).map_err(|e| e.to_string())?;
+            batch.put(address, encoded_account);
+        }
+        for (id, htlc) in block_state.htlcs {
+            let encoded_htlc = bincode::serialize(&htlc).map_err(|e| e.to_string())?;
+            batch.put(htlc_key(&id), encoded_htlc);
+        }
+        for (id, contract) in block_state.contracts {
+            let encoded_contract = bincode::serialize(&contract).map_err(|e| e.to_string())?;
+            batch.put(contract_key(&id), encoded_contract);
+        }
+        for ((id, storage_key), value) in block_state.contract_storage {
+            batch.put(contract_storage_key(&id, &storage_key), value);
+        }
+        for (id, asset) in block_state.assets {
+            let encoded_asset = bincode::serialize(&asset).map_err(|e| e.to_string())?;
+            batch.put(asset_key(&id), encoded_asset);
+        }
+        for ((id, address), balance) in block_state.asset_balances {
+            let encoded_balance = bincode::serialize(&balance).map_err(|e| e.to_string())?;
+            batch.put(asset_balance_key(&id, &address), encoded_balance);
+        }
+        for (id, nft) in block_state.nfts {
+            let encoded_nft = bincode::serialize(&nft).map_err(|e| e.to_string())?;
+            batch.put(nft_key(&id), encoded_nft);
+        }
+        for (name, record) in block_state.names {
+            let encoded_record = bincode::serialize(&record).map_err(|e| e.to_string())?;
+            batch.put(name_key(&name), encoded_record);
+        }
+        if let Some(params) = block_state.params {
+            let encoded_params = bincode::serialize(&params).map_err(|e| e.to_string())?;
+            batch.put(PARAMS_KEY, encoded_params);
+        }
+        for (id, proposal) in block_state.proposals {
+            let encoded_proposal = bincode::serialize(&proposal).map_err(|e| e.to_string())?;
+            batch.put(proposal_key(&id), encoded_proposal);
+        }
+        for (id, voter) in block_state.votes {
+            batch.put(vote_key(&id, &voter), []);
+        }
+        if let Some(total_stake) = block_state.total_stake {
+            batch.put(TOTAL_STAKE_KEY, total_stake.to_le_bytes());
+        }
+        for (height, id) in block_state.activations {
+            let mut key = activation_prefix(height);
+            key.extend_from_slice(&id);
+            batch.put(key, []);
+        }
+        for ((owner, id), owned) in block_state.nft_owner_index {
+            if owned {
+                batch.put(nft_owner_key(&owner, &id), []);
+            } else {
+                batch.delete(nft_owner_key(&owner, &id));
+            }
+        }
+        self.db.write(batch).map_err(|e| e.to_string())
+    }
+
+    fn account_in_block(
+        &self,
+        address: &Address,
+        temp_block_state: &BlockState,
+    ) -> Result<Option<Account>, String> {
+        if let Some(acc) = temp_block_state.accounts.get(address) {
+            return Ok(Some(acc.clone()));
+        }
+        self.get_account(address)
+            .map_err(|e| format!("STATE: Gagal membaca database akun: {}", e))
+    }
+
+    pub fn signing_key_in_block(&self, address: &Address, temp_block_state: &BlockState) -> Result<Option<TaggedPublicKey>, String> {
+        Ok(self
+            .account_in_block(address, temp_block_state)?
+            .and_then(|account| account.signing_key))
+    }
+
+    fn htlc_in_block(&self, id: &HtlcId, temp_block_state: &BlockState) -> Result<Htlc, String> {
+        if let Some(htlc) = temp_block_state.htlcs.get(id) {
+            return Ok(htlc.clone());
+        }
+        self.get_htlc(id)
+            .map_err(|e| format!("STATE: Gagal membaca database HTLC: {}", e))?
+            .ok_or_else(|| format!("STATE: HTLC 0x{} tidak ditemukan", hex::encode(id)))
+    }
+
+    fn contract_in_block(&self, id: &ContractId, temp_block_state: &BlockState) -> Result<Contract, String> {
+        if let Some(contract) = temp_block_state.contracts.get(id) {
+            return Ok(contract.clone());
+        }
+        self.get_contract(id)
+            .map_err(|e| format!("STATE: Gagal membaca database kontrak: {}", e))?
+            .ok_or_else(|| format!("STATE: Kontrak 0x{} tidak ditemukan", hex::encode(id)))
+    }
+
+    fn asset_in_block(&self, id: &AssetId, temp_block_state: &BlockState) -> Result<Asset, String> {
+        if let Some(asset) = temp_block_state.assets.get(id) {
+            return Ok(asset.clone());
+        }
+        self.get_asset(id)
+            .map_err(|e| format!("STATE: Gagal membaca database aset: {}", e))?
+            .ok_or_else(|| format!("STATE: Aset 0x{} tidak ditemukan", hex::encode(id)))
+    }
+
+    fn asset_balance_in_block(&self, id: &AssetId, address: &Address, temp_block_state: &BlockState) -> Result<u64, String> {
+        if let Some(balance) = temp_block_state.asset_balances.get(&(*id, *address)) {
+            return Ok(*balance);
+        }
+        self.get_asset_balance(id, address)
+            .map_err(|e| format!("STATE: Gagal membaca database saldo aset: {}", e))
+    }
+
+    fn nft_in_block(&self, id: &NftId, temp_block_state: &BlockState) -> Result<Nft, String> {
+        if let Some(nft) = temp_block_state.nfts.get(id) {
+            return Ok(nft.clone());
+        }
+        self.get_nft(id)
+            .map_err(|e| format!("STATE: Gagal membaca database NFT: {}", e))?
+            .ok_or_else(|| format!("STATE: NFT 0x{} tidak ditemukan", hex::encode(id)))
+    }
+
+    fn owned_nft_in_block(&self, id: &NftId, owner: &Address, temp_block_state: &BlockState) -> Result<Nft, String> {
+        let nft = self.nft_in_block(id, temp_block_state)?;
+        if nft.burned {
+            return Err(format!("STATE: NFT 0x{} sudah dibakar", hex::encode(id)));
+        }
+        if nft.owner != *owner {
+            return Err("STATE: Pengirim bukan pemilik NFT".to_string());
+        }
+        Ok(nft)
+    }
+
+    fn name_in_block(&self, name: &str, temp_block_state: &BlockState) -> Result<Option<NameRecord>, String> {
+        if let Some(record) = temp_block_state.names.get(name) {
+            return Ok(Some(record.clone()));
+        }
+        self.get_name(name)
+            .map_err(|e| format!("STATE: Gagal membaca database nama: {}", e))
+    }
+
+    fn owned_name_in_block(&self, name: &str, owner: &Address, block_height: u64, temp_block_state: &BlockState) -> Result<NameRecord, String> {
+        let record = self
+            .name_in_block(name, temp_block_state)?
+            .filter(|record| record.is_active(block_height))
+            .ok_or_else(|| format!("STATE: Nama '{}' tidak terdaftar atau sudah kedaluwarsa", name))?;
+        if record.owner != *owner {
+            return Err(format!("STATE: Pengirim bukan pemilik nama '{}'", name));
+        }
+        Ok(record)
+    }
+
+    pub fn params_in_block(&self, temp_block_state: &BlockState) -> Result<ChainParams, String> {
+        if let Some(params) = &temp_block_state.params {
+            return Ok(params.clone());
+        }
+        self.get_params()
+            .map_err(|e| format!("STATE: Gagal membaca parameter chain: {}", e))
+    }
+
+    fn total_stake_in_block(&self, temp_block_state: &BlockState) -> Result<u64, String> {
+        if let Some(total_stake) = temp_block_state.total_stake {
+            return Ok(total_stake);
+        }
+        self.get_total_stake()
+            .map_err(|e| format!("STATE: Gagal membaca total stake: {}", e))
+    }
+
+    fn proposal_in_block(&self, id: &ProposalId, temp_block_state: &BlockState) -> Result<Proposal, String> {
+        if let Some(proposal) = temp_block_state.proposals.get(id) {
+            return Ok(proposal.clone());
+        }
+        self.get_proposal(id)
+            .map_err(|e| format!("STATE: Gagal membaca database proposal: {}", e))?
+            .ok_or_else(|| format!("STATE: Proposal 0x{} tidak ditemukan", hex::encode(id)))
+    }
+
+    // Menghitung hasil proposal yang aktif di `block_height`. Dipanggil sekali
+    // di awal setiap blok, sebelum transaksinya divalidasi.
+    pub fn apply_activations(&self, block_height: u64, temp_block_state: &mut BlockState) -> Result<(), String> {
+        let prefix = activation_prefix(block_height);
+        let mut due = Vec::new();
+        for item in self.db.prefix_iterator(&prefix) {
+            let (key, _) = item.map_err(|e| format!("STATE: Gagal membaca indeks aktivasi: {}", e))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            if let Ok(id) = ProposalId::try_from(&key[prefix.len()..]) {
+                due.push(id);
+            }
+        }
+
+        for id in due {
+            let mut proposal = self.proposal_in_block(&id, temp_block_state)?;
+            if proposal.status != ProposalStatus::Voting {
+                continue;
+            }
+            let total_stake = self.total_stake_in_block(temp_block_state)? as u128;
+            let turnout = proposal.yes_stake as u128 + proposal.no_stake as u128;
+            let quorum = turnout * 100 >= total_stake * GOVERNANCE_QUORUM_PERCENT as u128;
+            let majority = proposal.yes_stake as u128 * 2 > total_stake;
+            let mut params = self.params_in_block(temp_block_state)?;
+            let change_check = params.check_change(&proposal.change);
+            if quorum && majority && change_check.is_ok() {
+                params.apply(&proposal.change);
+                info!("STATE: Proposal 0x{} aktif di tinggi {}: {:?}", hex::encode(id), block_height, proposal.change);
+                temp_block_state.params = Some(params);
+                proposal.status = ProposalStatus::Activated;
+            } else if let Err(e) = change_check {
+                info!("STATE: Proposal 0x{} ditolak saat aktivasi: {}", hex::encode(id), e);
+                proposal.status = ProposalStatus::Rejected;
+            } else {
+                info!(
+                    "STATE: Proposal 0x{} ditolak (setuju {}, menolak {}, total stake {})",
+                    hex::encode(id),
+                    proposal.yes_stake,
+                    proposal.no_stake,
+                    total_stake
+                );
+                proposal.status = ProposalStatus::Rejected;
+            }
+            temp_block_state.proposals.insert(id, proposal);
+        }
+        Ok(())
+    }
+
+    // Menjalankan kontrak dan, jika berhasil, menggabungkan perubahan storage dan
+    // transfer-nya ke state blok lalu mengembalikan `true`. Eksekusi yang gagal (trap,
+    // revert, kehabisan fuel) mengembalikan `false` tanpa mengubah apa pun: transaksi tetap
+    // masuk blok dan biayanya tetap dibayar, hanya efek kontraknya yang di-rollback.
+    fn run_contract(
+        &self,
+        contract_id: ContractId,
+        mut contract: Contract,
+        entry: &str,
+        required: bool,
+        ctx: ExecutionContext,
+        temp_block_state: &mut BlockState,
+    ) -> Result<bool, String> {
+        let pending_storage = temp_block_state
+            .contract_storage
+            .iter()
+            .filter(|((id, _), _)| *id == contract_id)
+            .map(|((_, key), value)| (key.clone(), value.clone()))
+            .collect();
+
+        let outcome = match contract::execute(Arc::clone(&self.db), pending_storage, &contract.code, entry, required, ctx) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                warn!("STATE: Eksekusi kontrak 0x{} di-rollback: {}", hex::encode(contract_id), e);
+                return Ok(false);
+            }
+        };
+        info!("STATE: Kontrak 0x{} dieksekusi, fuel terpakai {}", hex::encode(contract_id), outcome.fuel_used);
+
+        for (key, value) in outcome.storage_writes {
+            temp_block_state.contract_storage.insert((contract_id, key), value);
+        }
+        contract.balance = outcome.balance;
+        temp_block_state.contracts.insert(contract_id, contract);
+        for (recipient, amount) in outcome.transfers {
+            self.credit_in_block(&recipient, amount, temp_block_state)?;
+        }
+        Ok(true)
+    }
+
+    fn credit_in_block(
+        &self,
+        address: &Address,
+        amount: u64,
+        temp_block_state: &mut BlockState,
+    ) -> Result<(), String> {
+        let mut account = self
+            .account_in_block(address, temp_block_state)?
+            .unwrap_or_else(|| Account::new(0));
+        account.balance += amount;
+        temp_block_state.accounts.insert(*address, account);
+        Ok(())
+    }
+
     pub fn validate_transaction_in_block(
         &self,
         tx: &Transaction,
-        temp_block_state: &mut HashMap<Address, Account>,
+        block_height: u64,
+        temp_block_state: &mut BlockState,
     ) -> Result<(), String> {
-        let mut sender_account = if let Some(acc) = temp_block_state.get(&tx.sender) {
-            acc.clone()
-        } else {
-            self.get_account(&tx.sender)
-                .map_err(|e| format!("STATE: Gagal membaca database akun pengirim: {}", e))?
-                .ok_or_else(|| "STATE: Akun pengirim tidak ditemukan di database".to_string())?
-        };
+        let mut sender_account = self
+            .account_in_block(&tx.sender, temp_block_state)?
+            .ok_or_else(|| "STATE: Akun pengirim tidak ditemukan di database".to_string())?;
 
         if tx.nonce != sender_account.nonce {
             warn!("STATE: Nonce tidak valid (expected {}, got {}).", sender_account.nonce, tx.nonce);
             return Err("STATE: Nonce tidak valid".to_string());
         }
 
+        let min_fee = self.params_in_block(temp_block_state)?.min_fee;
+        if tx.fee < min_fee {
+            return Err(format!("STATE: Biaya transaksi di bawah minimum (minimum {}, got {})", min_fee, tx.fee));
+        }
+
         sender_account.nonce += 1;
 
         // Proses data transaksi sesuai jenisnya
         match &tx.data {
             TransactionData::Transfer { recipient, amount } => {
-                if sender_account.balance < *amount + tx.fee {
-                    return Err(format!("STATE: Saldo tidak cukup untuk transfer + biaya (butuh {}, punya {})", amount + tx.fee, sender_account.balance));
+                let required = amount
+                    .checked_add(tx.fee)
+                    .ok_or_else(|| "STATE: Jumlah transfer + biaya melebihi batas".to_string())?;
+                if sender_account.balance < required {
+                    return Err(format!("STATE: Saldo tidak cukup untuk transfer + biaya (butuh {}, punya {})", required, sender_account.balance));
                 }
-                sender_account.balance -= *amount + tx.fee;
-                
-                let mut recipient_account = if let Some(acc) = temp_block_state.get(recipient) {
-                    acc.clone()
-                } else {
-                    self.get_account(recipient)
-                        .map_err(|e| format!("STATE: Gagal membaca database akun penerima: {}", e))?
-                        .unwrap_or_else(|| Account::new(0))
-                };
-
-                recipient_account.balance += *amount;
-                temp_block_state.insert(*recipient, recipient_account);
+                sender_account.balance -= required;
+                // Simpan pengirim lebih dulu agar transfer ke diri sendiri tidak menimpa saldo.
+                temp_block_state.accounts.insert(tx.sender, sender_account);
+                return self.credit_in_block(recipient, *amount, temp_block_state);
+            }
+            TransactionData::TransferToName { name, amount } => {
+                let recipient = self
+                    .name_in_block(name, temp_block_state)?
+                    .filter(|record| record.is_active(block_height))
+                    .map(|record| record.owner)
+                    .ok_or_else(|| format!("STATE: Nama '{}' tidak terdaftar atau sudah kedaluwarsa", name))?;
+                let required = amount
+                    .checked_add(tx.fee)
+                    .ok_or_else(|| "STATE: Jumlah transfer + biaya melebihi batas".to_string())?;
+                if sender_account.balance < required {
+                    return Err(format!("STATE: Saldo tidak cukup untuk transfer + biaya (butuh {}, punya {})", required, sender_account.balance));
+                }
+                sender_account.balance -= required;
+                temp_block_state.accounts.insert(tx.sender, sender_account);
+                return self.credit_in_block(&recipient, *amount, temp_block_state);
             }
             TransactionData::Stake { amount } => {
-                if sender_account.balance < *amount + tx.fee {
-                    return Err(format!("STATE: Saldo tidak cukup untuk stake + biaya (butuh {}, punya {})", amount + tx.fee, sender_account.balance));
+                let required = amount
+                    .checked_add(tx.fee)
+                    .ok_or_else(|| "STATE: Jumlah stake + biaya melebihi batas".to_string())?;
+                if sender_account.balance < required {
+                    return Err(format!("STATE: Saldo tidak cukup untuk stake + biaya (butuh {}, punya {})", required, sender_account.balance));
                 }
-                sender_account.balance -= *amount + tx.fee;
+                sender_account.balance -= required;
                 sender_account.staked_amount += *amount;
+                let total_stake = self.total_stake_in_block(temp_block_state)?;
+                temp_block_state.total_stake = Some(total_stake.saturating_add(*amount));
+            }
+            TransactionData::HtlcLock { recipient, amount, hashlock, timeout_height } => {
+                if *timeout_height <= block_height {
+                    return Err(format!("STATE: Timeout HTLC ({}) harus di atas tinggi blok saat ini ({})", timeout_height, block_height));
+                }
+                let required = amount
+                    .checked_add(tx.fee)
+                    .ok_or_else(|| "STATE: Jumlah HTLC + biaya melebihi batas".to_string())?;
+                if sender_account.balance < required {
+                    return Err(format!("STATE: Saldo tidak cukup untuk HTLC + biaya (butuh {}, punya {})", required, sender_account.balance));
+                }
+                let id = tx.derived_id();
+                if temp_block_state.htlcs.contains_key(&id) || self.get_htlc(&id).map_err(|e| e.to_string())?.is_some() {
+                    return Err(format!("STATE: HTLC 0x{} sudah ada", hex::encode(id)));
+                }
+                sender_account.balance -= required;
+                temp_block_state.htlcs.insert(id, Htlc {
+                    sender: tx.sender,
+                    recipient: *recipient,
+                    amount: *amount,
+                    hashlock: *hashlock,
+                    timeout_height: *timeout_height,
+                    status: HtlcStatus::Locked,
+                });
+            }
+            TransactionData::HtlcClaim { htlc_id, preimage } => {
+                let mut htlc = self.htlc_in_block(htlc_id, temp_block_state)?;
+                if htlc.status != HtlcStatus::Locked {
+                    return Err("STATE: HTLC sudah diselesaikan".to_string());
+                }
+                if block_height >= htlc.timeout_height {
+                    return Err("STATE: HTLC sudah melewati timeout, hanya bisa di-refund".to_string());
+                }
+                if Sha256::digest(preimage).as_slice() != htlc.hashlock {
+                    return Err("STATE: Preimage tidak cocok dengan hashlock HTLC".to_string());
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+                temp_block_state.accounts.insert(tx.sender, sender_account);
+
+                htlc.status = HtlcStatus::Claimed { preimage: preimage.clone() };
+                self.credit_in_block(&htlc.recipient, htlc.amount, temp_block_state)?;
+                temp_block_state.htlcs.insert(*htlc_id, htlc);
+                return Ok(());
+            }
+            TransactionData::HtlcRefund { htlc_id } => {
+                let mut htlc = self.htlc_in_block(htlc_id, temp_block_state)?;
+                if htlc.status != HtlcStatus::Locked {
+                    return Err("STATE: HTLC sudah diselesaikan".to_string());
+                }
+                if block_height < htlc.timeout_height {
+                    return Err(format!("STATE: HTLC belum timeout (timeout di tinggi {}, sekarang {})", htlc.timeout_height, block_height));
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+                temp_block_state.accounts.insert(tx.sender, sender_account);
+
+                htlc.status = HtlcStatus::Refunded;
+                self.credit_in_block(&htlc.sender, htlc.amount, temp_block_state)?;
+                temp_block_state.htlcs.insert(*htlc_id, htlc);
+                return Ok(());
+            }
+            TransactionData::DeployContract { code, fuel_limit } => {
+                contract::validate_code(code).map_err(|e| format!("STATE: {}", e))?;
+                let contract_id = tx.derived_id();
+                if temp_block_state.contracts.contains_key(&contract_id) || self.get_contract(&contract_id).map_err(|e| e.to_string())?.is_some() {
+                    return Err(format!("STATE: Kontrak 0x{} sudah ada", hex::encode(contract_id)));
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+                temp_block_state.accounts.insert(tx.sender, sender_account);
+
+                let contract = Contract { creator: tx.sender, code: code.clone(), balance: 0 };
+                let ctx = ExecutionContext {
+                    contract_id,
+                    caller: tx.sender,
+                    value: 0,
+                    balance: 0,
+                    block_height,
+                    input: Vec::new(),
+                    fuel_limit: *fuel_limit,
+                };
+                self.run_contract(contract_id, contract, "init", false, ctx, temp_block_state)?;
+                return Ok(());
+            }
+            TransactionData::CallContract { contract_id, input, amount, fuel_limit } => {
+                let required = amount
+                    .checked_add(tx.fee)
+                    .ok_or_else(|| "STATE: Jumlah panggilan kontrak + biaya melebihi batas".to_string())?;
+                if sender_account.balance < required {
+                    return Err(format!("STATE: Saldo tidak cukup untuk panggilan kontrak + biaya (butuh {}, punya {})", required, sender_account.balance));
+                }
+                let contract = self.contract_in_block(contract_id, temp_block_state)?;
+                let contract_balance = contract
+                    .balance
+                    .checked_add(*amount)
+                    .ok_or_else(|| "STATE: Saldo kontrak melebihi batas".to_string())?;
+                sender_account.balance -= required;
+                temp_block_state.accounts.insert(tx.sender, sender_account);
+
+                let ctx = ExecutionContext {
+                    contract_id: *contract_id,
+                    caller: tx.sender,
+                    value: *amount,
+                    balance: contract_balance,
+                    block_height,
+                    input: input.clone(),
+                    fuel_limit: *fuel_limit,
+                };
+                if !self.run_contract(*contract_id, contract, "call", true, ctx, temp_block_state)? {
+                    // Nilai yang dikirim ke kontrak dikembalikan; biaya tetap dibayar.
+                    self.credit_in_block(&tx.sender, *amount, temp_block_state)?;
+                }
+                return Ok(());
+            }
+            TransactionData::CreateAsset { symbol, decimals, max_supply } => {
+                let valid_symbol = !symbol.is_empty()
+                    && symbol.len() <= MAX_ASSET_SYMBOL_LEN
+                    && symbol.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+                if !valid_symbol {
+                    return Err(format!("STATE: Simbol aset '{}' tidak valid", symbol));
+                }
+                if *decimals > MAX_ASSET_DECIMALS {
+                    return Err(format!("STATE: Desimal aset maksimal {}", MAX_ASSET_DECIMALS));
+                }
+                if *max_supply == 0 {
+                    return Err("STATE: Suplai maksimum aset harus lebih dari nol".to_string());
+                }
+                let asset_id = tx.derived_id();
+                if temp_block_state.assets.contains_key(&asset_id) || self.get_asset(&asset_id).map_err(|e| e.to_string())?.is_some() {
+                    return Err(format!("STATE: Aset 0x{} sudah ada", hex::encode(asset_id)));
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+                temp_block_state.assets.insert(asset_id, Asset {
+                    issuer: tx.sender,
+                    symbol: symbol.clone(),
+                    decimals: *decimals,
+                    max_supply: *max_supply,
+                    total_supply: 0,
+                });
+            }
+            TransactionData::MintAsset { asset_id, recipient, amount } => {
+                let mut asset = self.asset_in_block(asset_id, temp_block_state)?;
+                if asset.issuer != tx.sender {
+                    return Err("STATE: Hanya penerbit yang dapat mencetak aset".to_string());
+                }
+                let new_supply = asset.total_supply.checked_add(*amount)
+                    .filter(|supply| *supply <= asset.max_supply)
+                    .ok_or_else(|| format!("STATE: Pencetakan melebihi suplai maksimum aset ({})", asset.max_supply))?;
+                charge_fee(&mut sender_account, tx.fee)?;
+
+                asset.total_supply = new_supply;
+                let balance = self.asset_balance_in_block(asset_id, recipient, temp_block_state)?;
+                temp_block_state.asset_balances.insert((*asset_id, *recipient), balance + *amount);
+                temp_block_state.assets.insert(*asset_id, asset);
+            }
+            TransactionData::BurnAsset { asset_id, amount } => {
+                let mut asset = self.asset_in_block(asset_id, temp_block_state)?;
+                let balance = self.asset_balance_in_block(asset_id, &tx.sender, temp_block_state)?;
+                if balance < *amount {
+                    return Err(format!("STATE: Saldo aset tidak cukup untuk dibakar (butuh {}, punya {})", amount, balance));
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+
+                asset.total_supply -= *amount;
+                temp_block_state.asset_balances.insert((*asset_id, tx.sender), balance - *amount);
+                temp_block_state.assets.insert(*asset_id, asset);
+            }
+            TransactionData::TransferAsset { asset_id, recipient, amount } => {
+                self.asset_in_block(asset_id, temp_block_state)?;
+                let sender_balance = self.asset_balance_in_block(asset_id, &tx.sender, temp_block_state)?;
+                if sender_balance < *amount {
+                    return Err(format!("STATE: Saldo aset tidak cukup (butuh {}, punya {})", amount, sender_balance));
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+
+                temp_block_state.asset_balances.insert((*asset_id, tx.sender), sender_balance - *amount);
+                let recipient_balance = self.asset_balance_in_block(asset_id, recipient, temp_block_state)?;
+                temp_block_state.asset_balances.insert((*asset_id, *recipient), recipient_balance + *amount);
+            }
+            TransactionData::RegisterName { name } => {
+                validate_name(name).map_err(|e| format!("STATE: {}", e))?;
+                if let Some(record) = self.name_in_block(name, temp_block_state)? {
+                    if record.is_active(block_height) {
+                        return Err(format!("STATE: Nama '{}' sudah terdaftar sampai tinggi {}", name, record.expiry_height));
+                    }
+                }
+                charge_fee(&mut sender_account, name_fee(tx)?)?;
+                temp_block_state.names.insert(name.clone(), NameRecord {
+                    owner: tx.sender,
+                    expiry_height: block_height + NAME_REGISTRATION_PERIOD,
+                });
+            }
+            TransactionData::RenewName { name } => {
+                let mut record = self.owned_name_in_block(name, &tx.sender, block_height, temp_block_state)?;
+                charge_fee(&mut sender_account, name_fee(tx)?)?;
+                record.expiry_height += NAME_REGISTRATION_PERIOD;
+                temp_block_state.names.insert(name.clone(), record);
+            }
+            TransactionData::TransferName { name, new_owner } => {
+                let mut record = self.owned_name_in_block(name, &tx.sender, block_height, temp_block_state)?;
+                charge_fee(&mut sender_account, tx.fee)?;
+                record.owner = *new_owner;
+                temp_block_state.names.insert(name.clone(), record);
+            }
+            TransactionData::RotateKey { new_key } => {
+                if !new_key.is_valid() {
+                    return Err("STATE: Kunci publik baru tidak valid".to_string());
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+                info!("STATE: Kunci penandatangan untuk alamat 0x{}... dirotasi ke {:?}", hex::encode(&tx.sender[..8]), new_key.scheme);
+                sender_account.signing_key = rotated_signing_key(&tx.sender, new_key);
+            }
+            TransactionData::Propose { change, activation_height } => {
+                if sender_account.staked_amount == 0 {
+                    return Err("STATE: Hanya akun dengan stake yang dapat membuat proposal".to_string());
+                }
+                if *activation_height < block_height + MIN_VOTING_PERIOD {
+                    return Err(format!("STATE: Tinggi aktivasi minimal {}", block_height + MIN_VOTING_PERIOD));
+                }
+                self.params_in_block(temp_block_state)?.check_change(change)?;
+                charge_fee(&mut sender_account, tx.fee)?;
+
+                let proposal_id = tx.derived_id();
+                temp_block_state.proposals.insert(proposal_id, Proposal {
+                    proposer: tx.sender,
+                    change: change.clone(),
+                    created_height: block_height,
+                    activation_height: *activation_height,
+                    yes_stake: 0,
+                    no_stake: 0,
+                    status: ProposalStatus::Voting,
+                });
+                temp_block_state.activations.insert((*activation_height, proposal_id));
+            }
+            TransactionData::Vote { proposal_id, approve } => {
+                let mut proposal = self.proposal_in_block(proposal_id, temp_block_state)?;
+                if proposal.status != ProposalStatus::Voting || block_height >= proposal.activation_height {
+                    return Err("STATE: Masa voting proposal sudah berakhir".to_string());
+                }
+                if sender_account.staked_amount == 0 {
+                    return Err("STATE: Hanya akun dengan stake yang dapat memberikan suara".to_string());
+                }
+                if temp_block_state.votes.contains(&(*proposal_id, tx.sender)) || self.has_voted(proposal_id, &tx.sender)? {
+                    return Err("STATE: Akun sudah memberikan suara untuk proposal ini".to_string());
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+
+                if *approve {
+                    proposal.yes_stake += sender_account.staked_amount;
+                } else {
+                    proposal.no_stake += sender_account.staked_amount;
+                }
+                temp_block_state.proposals.insert(*proposal_id, proposal);
+                temp_block_state.votes.insert((*proposal_id, tx.sender));
+            }
+            TransactionData::MintNft { metadata_hash } => {
+                let nft_id = tx.derived_id();
+                if temp_block_state.nfts.contains_key(&nft_id) || self.get_nft(&nft_id).map_err(|e| e.to_string())?.is_some() {
+                    return Err(format!("STATE: NFT 0x{} sudah ada", hex::encode(nft_id)));
+                }
+                charge_fee(&mut sender_account, tx.fee)?;
+                temp_block_state.nfts.insert(nft_id, Nft {
+                    creator: tx.sender,
+                    owner: tx.sender,
+                    metadata_hash: *metadata_hash,
+                    burned: false,
+                    history: vec![NftEvent { block_height, kind: NftEventKind::Minted }],
+                });
+                temp_block_state.nft_owner_index.insert((tx.sender, nft_id), true);
+            }
+            TransactionData::TransferNft { nft_id, recipient } => {
+                let mut nft = self.owned_nft_in_block(nft_id, &tx.sender, temp_block_state)?;
+                charge_fee(&mut sender_account, tx.fee)?;
+
+                nft.owner = *recipient;
+                nft.history.push(NftEvent {
+                    block_height,
+                    kind: NftEventKind::Transferred { from: tx.sender, to: *recipient },
+                });
+                temp_block_state.nfts.insert(*nft_id, nft);
+                temp_block_state.nft_owner_index.insert((tx.sender, *nft_id), false);
+                temp_block_state.nft_owner_index.insert((*recipient, *nft_id), true);
+            }
+            TransactionData::BurnNft { nft_id } => {
+                let mut nft = self.owned_nft_in_block(nft_id, &tx.sender, temp_block_state)?;
+                charge_fee(&mut sender_account, tx.fee)?;
+
+                nft.burned = true;
+                nft.history.push(NftEvent { block_height, kind: NftEventKind::Burned });
+                temp_block_state.nfts.insert(*nft_id, nft);
+                temp_block_state.nft_owner_index.insert((tx.sender, *nft_id), false);
             }
         }
 
-        temp_block_state.insert(tx.sender, sender_account);
+        temp_block_state.accounts.insert(tx.sender, sender_account);
         Ok(())
     }
 
-    pub fn bootstrap_accounts(&self) {
-        let genesis_keypair = crate::crypto::KeyPair::new();
-        let voter_keypair = crate::crypto::KeyPair::new();
+    // Kunci akun bootstrap disimpan sebagai keystore terenkripsi di `keystore_dir`,
+    // bukan dicetak ke log.
+    pub fn bootstrap_accounts(&self, keystore_dir: &std::path::Path, password: &str) -> Result<(), String> {
+        std::fs::create_dir_all(keystore_dir)
+            .map_err(|e| format!("Gagal membuat direktori keystore {}: {}", keystore_dir.display(), e))?;
+        let genesis_path = keystore_dir.join("genesis.json");
+        let voter_path = keystore_dir.join("voter.json");
+        let genesis_keypair = crate::crypto::create_keystore(&genesis_path, crate::crypto::SignatureScheme::Dilithium2, password)?;
+        let voter_keypair = crate::crypto::create_keystore(&voter_path, crate::crypto::SignatureScheme::Dilithium2, password)?;
 
         let genesis_account = Account::new(1_000_000_000);
         self.set_account(&genesis_keypair.public_key_bytes(), &genesis_account)
@@ -124,9 +1403,9 @@ impl StateMachine {
         self.set_account(&voter_keypair.public_key_bytes(), &voter_account)
             .unwrap();
 
-        info!("Dumping bootstrap keys (SAVE THESE!):");
-        info!("  Genesis Address: 0x{}", hex::encode(genesis_keypair.public_key_bytes()));
-        info!("  Voter Address:   0x{}", hex::encode(voter_keypair.public_key_bytes()));
-        info!("  Voter Private Key (for signing transactions): 0x{}", hex::encode(voter_keypair.private_key_bytes()));
+        info!("Akun bootstrap dibuat (keystore terenkripsi, SIMPAN FILE INI!):");
+        info!("  Genesis Address: 0x{}... -> {}", hex::encode(&genesis_keypair.public_key_bytes()[..8]), genesis_path.display());
+        info!("  Voter Address:   0x{}... -> {}", hex::encode(&voter_keypair.public_key_bytes()[..8]), voter_path.display());
+        Ok(())
     }
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
 // tests/full_flow.rs
 
 use evice_blockchain::{
-    blockchain::{Blockchain, Transaction},
-    crypto::{KeyPair, SIGNATURE_SIZE}, 
+    blockchain::{Blockchain, Transaction, TransactionData},
+    crypto::{KeyPair, SignatureScheme},
     mempool::Mempool,
     state::{Account, Address},
 };
@@ -18,22 +18,26 @@ fn test_full_transaction_to_block_flow() {
     let recipient_address: Address = KeyPair::new().public_key_bytes(); 
 
     let mut blockchain = Blockchain::new(db_path);
-    let mempool = Mempool::new();
+    let mempool = Mempool::with_cache(blockchain.signature_cache.clone());
 
-    let initial_user_account = Account { balance: 1000, nonce: 0 };
+    let initial_user_account = Account::new(1000);
     blockchain.state.set_account(&user_keys.public_key_bytes(), &initial_user_account).unwrap();
     
     let mut tx = Transaction {
         sender: user_keys.public_key_bytes(), 
-        recipient: recipient_address,
-        amount: 150,
+        data: TransactionData::Transfer { recipient: recipient_address, amount: 150 },
+        fee: 0,
         nonce: 0,
-        signature: [0; SIGNATURE_SIZE], 
+        signer: None,
+        signature_scheme: SignatureScheme::Dilithium2,
+        signature: Vec::new(),
     };
     let hash = tx.message_hash();
     tx.signature = user_keys.sign(&hash); 
 
+    let tx_hash = tx.hash();
     assert!(mempool.add_transaction(tx, &blockchain.state).is_ok());
+    assert_eq!(blockchain.signature_cache.len(), 1, "Verifikasi mempool seharusnya tercatat di cache bersama");
 
     let transactions_for_block = mempool.get_transactions(1);
     assert_eq!(transactions_for_block.len(), 1);
@@ -43,6 +47,7 @@ fn test_full_transaction_to_block_flow() {
     assert!(result, "Penambahan blok seharusnya berhasil");
 
     assert_eq!(blockchain.chain.len(), 2, "Blockchain seharusnya memiliki 2 blok (genesis + 1)");
+    assert!(blockchain.signature_cache.is_empty(), "Entri cache 0x{}... seharusnya dihapus setelah masuk blok", hex::encode(&tx_hash[..4]));
 
     let final_user_account = blockchain.state.get_account(&user_keys.public_key_bytes()).unwrap().unwrap();
     assert_eq!(final_user_account.balance, 850, "Saldo pengirim seharusnya berkurang");